@@ -1,23 +1,33 @@
 use crate::logic_hook::{GameContext, GameLoop, SynchronousLoop};
-use crate::world::constraints::ConstraintWidget;
-use crate::world::{GameContent, Solver, WorldSnapshot};
+use physics::constraints::ConstraintWidget;
+use physics::determinism::{TickHash, write_hash_stream};
+use physics::persistence::SavedState;
+use physics::scene::Scene;
+use physics::solvers::{SolveError, Solver, SolverRegistry};
+use physics::{AdaptiveStepping, GameContent, WorldSnapshot};
 use egui::{Color32, Frame, Pos2, Shape, Stroke, Ui, WidgetText, vec2};
 use egui_dock::{NodeIndex, TabViewer};
 use egui_plot::{Legend, Line, Plot, PlotPoint};
-use nalgebra::Vector2;
+use nalgebra::{Rotation2, Vector2};
 use running_context::event_handling::EguiGuiExtendContext;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct GameCore;
 
 impl GameCore {
-    pub fn new(time_step: Duration) -> (Gui, LogicLoop) {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(time_step: Duration, solvers: SolverRegistry) -> (Gui, LogicLoop) {
         let (graphic_sender, graphic_receiver) = std::sync::mpsc::channel();
         let (event_sender, event_receiver) = std::sync::mpsc::channel();
+        let (status_sender, status_receiver) = std::sync::mpsc::channel();
         (
-            Gui::new(graphic_receiver, event_sender),
-            LogicLoop::new(graphic_sender, event_receiver, time_step),
+            Gui::new(graphic_receiver, event_sender, status_receiver, solvers.clone()),
+            LogicLoop::new(graphic_sender, event_receiver, status_sender, time_step, solvers),
         )
     }
 }
@@ -30,42 +40,76 @@ enum Tab {
     Stats,
 }
 
-struct Event {
-    simulation: SimulationContent,
-    solver: Solver,
+/// where the GUI saves the state and loads it from, in the current directory
+const STATE_FILE: &str = "state.ron";
+
+/// every ``.ron`` file in it is listed in the main tab, in the current directory
+const SCENE_DIRECTORY: &str = "scenes";
+
+/// written in ``SCENE_DIRECTORY`` when it doesn't exist yet, on Android for example
+const BUILT_IN_SCENES: [(&str, &str); 16] = [
+    ("simple", include_str!("../../../scenes/simple.ron")),
+    ("double", include_str!("../../../scenes/double.ron")),
+    ("triple", include_str!("../../../scenes/triple.ron")),
+    ("rope", include_str!("../../../scenes/rope.ron")),
+    ("floor", include_str!("../../../scenes/floor.ron")),
+    ("rail", include_str!("../../../scenes/rail.ron")),
+    ("structure", include_str!("../../../scenes/structure.ron")),
+    ("pulley", include_str!("../../../scenes/pulley.ron")),
+    ("pulley_and_rail", include_str!("../../../scenes/pulley_and_rail.ron")),
+    ("hinged_bars", include_str!("../../../scenes/hinged_bars.ron")),
+    ("gears", include_str!("../../../scenes/gears.ron")),
+    ("stack", include_str!("../../../scenes/stack.ron")),
+    ("impact", include_str!("../../../scenes/impact.ron")),
+    ("bridge_soft", include_str!("../../../scenes/bridge_soft.ron")),
+    ("bridge", include_str!("../../../scenes/bridge.ron")),
+    ("driven", include_str!("../../../scenes/driven.ron")),
+];
+
+/// the names of the scenes in ``SCENE_DIRECTORY``, sorted, the directory is filled with ``BUILT_IN_SCENES`` if missing
+fn list_scenes() -> io::Result<Vec<String>> {
+    let directory = Path::new(SCENE_DIRECTORY);
+    if !directory.exists() {
+        std::fs::create_dir_all(directory)?;
+        for (name, text) in BUILT_IN_SCENES {
+            std::fs::write(directory.join(name).with_extension("ron"), text)?;
+        }
+    }
+    let mut scenes = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "ron")
+            && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+        {
+            scenes.push(name.to_string());
+        }
+    }
+    scenes.sort();
+    Ok(scenes)
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum SimulationContent {
-    Simple,
-    Double,
-    Triple,
-    Rope,
-    Rail,
-    Structure,
-    Pulley,
-    PulleyAndRail,
-    Bridge,
-    BridgeSoft,
+enum Event {
+    /// build the simulation again with new settings
+    Reset(Settings),
+    /// write the current state in ``STATE_FILE``
+    SaveState,
+    /// replace the simulation by a saved one
+    LoadState(SavedState),
 }
 
-impl SimulationContent {
-    const LIST: [SimulationContent; 10] = [
-        SimulationContent::Simple,
-        SimulationContent::Double,
-        SimulationContent::Triple,
-        SimulationContent::Rope,
-        SimulationContent::Rail,
-        SimulationContent::Structure,
-        SimulationContent::Pulley,
-        SimulationContent::PulleyAndRail,
-        SimulationContent::Bridge,
-        SimulationContent::BridgeSoft,
-    ];
+struct Settings {
+    scene: Scene,
+    solver: Arc<dyn Solver>,
+    substeps: usize,
+    parallel_islands: bool,
+    adaptive_stepping: bool,
+    deterministic: bool,
 }
 
 pub struct Gui {
     graphic_receiver: Receiver<WorldSnapshot>,
+    /// what the logic loop has to say about saving and loading the state
+    status_receiver: Receiver<String>,
     dock_viewer: DockViewer,
     tree: egui_dock::DockState<Tab>,
 }
@@ -81,36 +125,86 @@ impl Gui {
         tree
     }
 
-    fn new(receiver: Receiver<WorldSnapshot>, event_sender: Sender<Event>) -> Self {
-        Self {
+    fn new(
+        receiver: Receiver<WorldSnapshot>,
+        event_sender: Sender<Event>,
+        status_receiver: Receiver<String>,
+        solvers: SolverRegistry,
+    ) -> Self {
+        let selected_solver = solvers
+            .get("HybridV3")
+            .or_else(|| solvers.iter().next().cloned())
+            .expect("at least one solver should be registered");
+        let (scenes, status) = match list_scenes() {
+            Ok(scenes) => (scenes, None),
+            Err(error) => (vec![], Some(format!("couldn't list the scenes in {SCENE_DIRECTORY}: {error}"))),
+        };
+        let selected_scene = scenes
+            .iter()
+            .find(|scene| *scene == "double")
+            .or_else(|| scenes.first())
+            .cloned()
+            .unwrap_or_default();
+        let mut gui = Self {
             graphic_receiver: receiver,
+            status_receiver,
             dock_viewer: DockViewer {
                 snapshot: WorldSnapshot::default(),
+                previous_snapshot: None,
                 sender: event_sender,
                 kinetic_energy: vec![],
                 potential_energy: vec![],
                 elastic_energy: vec![],
                 mechanical_energy: vec![],
                 precision_factor: vec![],
-                selected_simulation: SimulationContent::Double,
-                selected_solver: Solver::HybridV3,
+                scenes,
+                selected_scene,
+                selected_solver,
+                solvers,
+                substeps: 4,
+                parallel_islands: false,
+                adaptive_stepping: false,
+                deterministic: false,
+                hashes: vec![],
+                status,
                 should_clear_graph: false,
             },
             tree: Self::default_view() //egui_dock::DockState::new(vec![Tab::World, Tab::Button, Tab::Plots, Tab::Stats]).,
+        };
+        if !gui.dock_viewer.selected_scene.is_empty() {
+            gui.dock_viewer.reset();
         }
+        gui
     }
 }
 
 struct DockViewer {
     snapshot: WorldSnapshot,
+    /// the snapshot before ``snapshot``, the bodies are drawn between the two
+    previous_snapshot: Option<WorldSnapshot>,
     sender: Sender<Event>,
     kinetic_energy: Vec<PlotPoint>,
     potential_energy: Vec<PlotPoint>,
     elastic_energy: Vec<PlotPoint>,
     mechanical_energy: Vec<PlotPoint>,
     precision_factor: Vec<PlotPoint>,
-    selected_simulation: SimulationContent,
-    selected_solver: Solver,
+    /// the scenes found in ``SCENE_DIRECTORY``
+    scenes: Vec<String>,
+    selected_scene: String,
+    selected_solver: Arc<dyn Solver>,
+    solvers: SolverRegistry,
+    /// only used by the sub-stepping solvers
+    substeps: usize,
+    /// solve the independent groups of bodies on several threads
+    parallel_islands: bool,
+    /// split the ticks where a single step isn't accurate enough
+    adaptive_stepping: bool,
+    /// hash the state every tick, to compare runs on different architectures
+    deterministic: bool,
+    /// the hash of every snapshot received since the simulation was loaded
+    hashes: Vec<TickHash>,
+    /// where the last export or save went, or why it failed
+    status: Option<String>,
     should_clear_graph: bool,
 }
 
@@ -142,48 +236,144 @@ impl DockViewer {
         ui.horizontal(|ui| {
             ui.vertical(|ui| {
                 ui.label("Simulation");
-                for simulation_content in SimulationContent::LIST.into_iter() {
-                    if ui
-                        .selectable_value(
-                            &mut self.selected_simulation,
-                            simulation_content,
-                            format!("{:?}", simulation_content),
-                        )
-                        .clicked()
-                    {
+                for scene in &self.scenes {
+                    if ui.selectable_label(self.selected_scene == *scene, scene).clicked() {
+                        self.selected_scene = scene.clone();
                         send_event = true;
                     }
                 }
+                if ui.button("rescan").clicked() {
+                    match list_scenes() {
+                        Ok(scenes) => self.scenes = scenes,
+                        Err(error) => {
+                            self.status = Some(format!("couldn't list the scenes in {SCENE_DIRECTORY}: {error}"))
+                        }
+                    }
+                }
             });
             ui.vertical(|ui| {
                 ui.label("Solver");
-                for solver in Solver::LIST.into_iter() {
+                for solver in self.solvers.iter() {
                     if ui
-                        .selectable_value(
-                            &mut self.selected_solver,
-                            solver,
-                            format!("{:?}", solver),
-                        )
+                        .selectable_label(self.selected_solver.name() == solver.name(), solver.name())
                         .clicked()
                     {
+                        self.selected_solver = solver.clone();
                         send_event = true;
                     }
                 }
+                if ui
+                    .add(egui::Slider::new(&mut self.substeps, 1..=32).text("substeps"))
+                    .changed()
+                {
+                    send_event = true;
+                }
+                if ui.checkbox(&mut self.parallel_islands, "parallel islands").changed() {
+                    send_event = true;
+                }
+                if ui.checkbox(&mut self.adaptive_stepping, "adaptive time step").changed() {
+                    send_event = true;
+                }
+                if ui.checkbox(&mut self.deterministic, "deterministic").changed() {
+                    send_event = true;
+                }
+                if ui
+                    .add_enabled(!self.hashes.is_empty(), egui::Button::new("export hashes"))
+                    .clicked()
+                {
+                    self.status = Some(self.export_hashes());
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("save state").clicked() {
+                        self.sender.send(Event::SaveState).unwrap();
+                    }
+                    if ui.button("load state").clicked() {
+                        self.load_state();
+                    }
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
             });
         });
         if send_event {
-            self.sender
-                .send(Event {
-                    simulation: self.selected_simulation,
-                    solver: self.selected_solver,
-                })
-                .unwrap();
+            self.reset();
         }
+        if let Some(error) = &self.snapshot.error {
+            ui.colored_label(
+                Color32::RED,
+                format!("the simulation stopped: {error}, select a simulation or a solver to restart"),
+            );
+        }
+        ui.label("- the simulations are the .ron files of the scenes directory, a new file shows up after a rescan, a modified one is read again when it is selected");
         ui.label("- soft simulations are only soft if the solver supports it, otherwise they are rigid");
+        ui.label("- substeps are only used by the sub-stepping solvers (Tgs)");
+        ui.label("- parallel islands solves the independent groups of bodies (separate piles, mechanisms that don't touch) on several threads");
+        ui.label("- save state writes the whole simulation in state.ron, load state goes on from there with the solver and the settings it was saved with");
+        ui.label("- deterministic hashes the state every tick, export the hashes of a run on two devices and diff them to find the first tick where they differ");
+        ui.label("- adaptive time step splits a tick in shorter steps when one step and two half steps disagree, the stats tab shows how many were needed");
         ui.label("- precision factor is the number of zero after the decimal point in the mean violation of the constraints, it doesn't have any mean if the simulation have soft parts. It's a good indicator of the precision of the simulation, the higher the better.");
         ui.label("- the mechanical energy is the sum of the kinetic, potential and elastic energy, it should be constant in a perfect simulation.");
     }
 
+    /// read the selected scene again, so the changes made to its file are taken, and rebuild the simulation with it
+    fn reset(&mut self) {
+        let path = Path::new(SCENE_DIRECTORY).join(&self.selected_scene).with_extension("ron");
+        let scene = match std::fs::read_to_string(&path) {
+            Ok(text) => Scene::from_ron(&text).map_err(|error| error.to_string()),
+            Err(error) => Err(error.to_string()),
+        };
+        match scene {
+            Ok(scene) => self
+                .sender
+                .send(Event::Reset(Settings {
+                    scene,
+                    solver: self.selected_solver.clone(),
+                    substeps: self.substeps,
+                    parallel_islands: self.parallel_islands,
+                    adaptive_stepping: self.adaptive_stepping,
+                    deterministic: self.deterministic,
+                }))
+                .unwrap(),
+            Err(error) => self.status = Some(format!("couldn't read {}: {error}", path.display())),
+        }
+    }
+
+    /// write the hashes received so far in the current directory, returns what to tell the user
+    fn export_hashes(&self) -> String {
+        let path = format!("hashes_{}_{}.txt", self.selected_scene, self.selected_solver.name());
+        let header = format!("{} {}", self.selected_scene, self.selected_solver.name());
+        let written = File::create(&path).and_then(|file| write_hash_stream(&header, &self.hashes, BufWriter::new(file)));
+        match written {
+            Ok(()) => format!("{} hashes written to {path}", self.hashes.len()),
+            Err(error) => format!("couldn't write {path}: {error}"),
+        }
+    }
+
+    /// read ``STATE_FILE`` and send it to the logic loop, the solver and the settings saved with it are selected
+    fn load_state(&mut self) {
+        let state = match std::fs::read_to_string(STATE_FILE) {
+            Ok(text) => SavedState::from_ron(&text).map_err(|error| error.to_string()),
+            Err(error) => Err(error.to_string()),
+        };
+        let state = match state {
+            Ok(state) => state,
+            Err(error) => {
+                self.status = Some(format!("couldn't read {STATE_FILE}: {error}"));
+                return;
+            }
+        };
+        if let Some(solver) = self.solvers.get(&state.solver) {
+            self.selected_solver = solver;
+        }
+        self.substeps = state.substeps;
+        self.adaptive_stepping = state.adaptive_stepping.is_some();
+        self.parallel_islands = state.parallel_islands;
+        self.deterministic = state.deterministic;
+        self.hashes.clear();
+        self.sender.send(Event::LoadState(state)).unwrap();
+    }
+
     fn draw_simulation(&self, ui: &mut Ui) {
         Frame::canvas(ui.style()).show(ui, |ui| {
             let desired_size = vec2(ui.available_width(), ui.available_height());
@@ -197,6 +387,14 @@ impl DockViewer {
                 p
             };
 
+            let (positions, angles) = self.interpolated_bodies();
+
+            // world position of a point attached to a body
+            let attachment = |index: usize, local: Vector2<f32>| {
+                let angle = angles[index].unwrap_or(0.0);
+                positions[index] + Rotation2::new(angle) * local
+            };
+
             let mut shapes = vec![];
 
             for (widgets, force) in self.snapshot.links.iter() {
@@ -207,16 +405,16 @@ impl DockViewer {
                 };
 
                 match widgets {
-                    ConstraintWidget::Link(a, b) => {
-                        let pos_a = self.snapshot.pos[*a];
-                        let pos_b = self.snapshot.pos[*b];
+                    ConstraintWidget::Link(a, local_a, b, local_b) => {
+                        let pos_a = attachment(*a, *local_a);
+                        let pos_b = attachment(*b, *local_b);
                         shapes.push(Shape::line_segment(
                             [to_screen_coordinates(pos_a), to_screen_coordinates(pos_b)],
                             Stroke::new(3.0, lerp_color),
                         ));
                     }
-                    ConstraintWidget::Anchor(a, anchor) => {
-                        let pos = to_screen_coordinates(self.snapshot.pos[*a]);
+                    ConstraintWidget::Anchor(a, local, anchor) => {
+                        let pos = to_screen_coordinates(attachment(*a, *local));
                         let anchor = to_screen_coordinates(*anchor);
 
                         shapes.push(Shape::line_segment(
@@ -257,8 +455,8 @@ impl DockViewer {
                         }
                     }
                     ConstraintWidget::Pulley(a, b, anchor_a, anchor_b) => {
-                        let pos_a = to_screen_coordinates(self.snapshot.pos[*a]);
-                        let pos_b = to_screen_coordinates(self.snapshot.pos[*b]);
+                        let pos_a = to_screen_coordinates(positions[*a]);
+                        let pos_b = to_screen_coordinates(positions[*b]);
                         let anchor_a = to_screen_coordinates(*anchor_a);
                         let anchor_b = to_screen_coordinates(*anchor_b);
 
@@ -273,19 +471,111 @@ impl DockViewer {
                         shapes.push(Shape::circle_filled(anchor_a, 5.0, Color32::BLUE));
                         shapes.push(Shape::circle_filled(anchor_b, 5.0, Color32::BLUE));
                     }
+                    ConstraintWidget::Hinge(a, local_a, b, local_b) => {
+                        // the lever arms show the shape of the bodies
+                        let pos_a = to_screen_coordinates(positions[*a]);
+                        let pos_b = to_screen_coordinates(positions[*b]);
+                        let joint_a = to_screen_coordinates(attachment(*a, *local_a));
+                        let joint_b = to_screen_coordinates(attachment(*b, *local_b));
+                        shapes.push(Shape::line_segment(
+                            [pos_a, joint_a],
+                            Stroke::new(3.0, lerp_color),
+                        ));
+                        shapes.push(Shape::line_segment(
+                            [pos_b, joint_b],
+                            Stroke::new(3.0, lerp_color),
+                        ));
+                        shapes.push(Shape::circle_filled(joint_a, 4.0, Color32::GRAY));
+                    }
+                    ConstraintWidget::Pin(a, local, anchor) => {
+                        let pos = to_screen_coordinates(positions[*a]);
+                        let joint = to_screen_coordinates(attachment(*a, *local));
+                        let anchor = to_screen_coordinates(*anchor);
+                        shapes.push(Shape::line_segment(
+                            [pos, joint],
+                            Stroke::new(3.0, lerp_color),
+                        ));
+                        shapes.push(Shape::circle_filled(anchor, 5.0, Color32::BLUE));
+                    }
+                    ConstraintWidget::Gear(a, b) => {
+                        let pos_a = to_screen_coordinates(positions[*a]);
+                        let pos_b = to_screen_coordinates(positions[*b]);
+                        shapes.push(Shape::line_segment(
+                            [pos_a, pos_b],
+                            Stroke::new(1.0, Color32::DARK_GRAY),
+                        ));
+                    }
 
                     _ => (),
                 }
             }
             shapes.extend(
-                self.snapshot.pos.iter().map(|body| {
+                positions.iter().map(|body| {
                     Shape::circle_filled(to_screen_coordinates(*body), 7.0, Color32::RED)
                 }),
             );
+            // rigid bodies get a tick showing their orientation
+            for (pos, angle) in positions.iter().zip(angles.iter()) {
+                let Some(angle) = angle else {
+                    continue;
+                };
+                let tip = pos + Rotation2::new(*angle) * Vector2::new(0.2, 0.0);
+                shapes.push(Shape::line_segment(
+                    [to_screen_coordinates(*pos), to_screen_coordinates(tip)],
+                    Stroke::new(2.0, Color32::RED),
+                ));
+            }
+            // colliders are drawn with their real size
+            for (pos, radius) in positions.iter().zip(self.snapshot.radii.iter()) {
+                let Some(radius) = radius else {
+                    continue;
+                };
+                shapes.push(Shape::circle_stroke(
+                    to_screen_coordinates(*pos),
+                    radius * 70.0,
+                    Stroke::new(2.0, Color32::LIGHT_RED),
+                ));
+            }
             ui.painter().extend(shapes);
         });
     }
 
+    /// Position and orientation of the bodies one tick behind the latest snapshot, interpolated between the last two.
+    /// Lagging a tick behind means there is always a snapshot on each side of the time drawn,
+    /// the motion stays smooth whatever the frame rate
+    fn interpolated_bodies(&self) -> (Vec<Vector2<f32>>, Vec<Option<f32>>) {
+        let latest = &self.snapshot;
+        let latest_bodies = || (latest.pos.clone(), latest.angles.clone());
+        // a new simulation, or a tick that got lost, there is nothing to interpolate from
+        let Some(previous) = self
+            .previous_snapshot
+            .as_ref()
+            .filter(|previous| previous.date + 1 == latest.date && previous.pos.len() == latest.pos.len())
+        else {
+            return latest_bodies();
+        };
+        let (Some(previous_time), Some(latest_time)) = (previous.timestamp, latest.timestamp) else {
+            return latest_bodies();
+        };
+        let tick = latest_time.saturating_duration_since(previous_time).as_secs_f32();
+        if tick == 0.0 {
+            return latest_bodies();
+        }
+        let t = (Instant::now().saturating_duration_since(latest_time).as_secs_f32() / tick).min(1.0);
+
+        let positions = previous.pos.iter().zip(&latest.pos).map(|(a, b)| a.lerp(b, t)).collect();
+        let angles = previous
+            .angles
+            .iter()
+            .zip(&latest.angles)
+            .map(|(a, b)| match (a, b) {
+                (Some(a), Some(b)) => Some(a + (b - a) * t),
+                _ => *b,
+            })
+            .collect();
+        (positions, angles)
+    }
+
     fn draw_plot(&mut self, ui: &mut Ui) {
         let mut plot = Plot::new("energy over time").legend(Legend::default());
 
@@ -315,6 +605,23 @@ impl DockViewer {
             "time taken to solve: {:?}",
             self.snapshot.calculation_time
         ));
+        if !self.snapshot.redundant_constraints.is_empty() {
+            ui.label(format!(
+                "redundant constraints (no force applied): {:?}",
+                self.snapshot.redundant_constraints
+            ));
+        }
+        ui.label(format!("islands: {}", self.snapshot.islands));
+        ui.label(format!("steps in the last tick: {}", self.snapshot.tick_substeps));
+        if let Some(hash) = self.snapshot.state_hash {
+            ui.label(format!("state hash: {hash:016x}"));
+        }
+        if let Some(stats) = &self.snapshot.iteration_stats {
+            ui.label(format!(
+                "iterative solver: {} iterations over {} solves, {} stopped at their cap, residual {:e}",
+                stats.iterations, stats.solves, stats.unconverged, stats.residual
+            ));
+        }
 
         let mut plot = Plot::new("precision over time").legend(Legend::default());
 
@@ -339,8 +646,19 @@ impl SynchronousLoop for Gui {
             self.dock_viewer.precision_factor.clear();
         }
 
+        if let Some(status) = self.status_receiver.try_iter().last() {
+            self.dock_viewer.status = Some(status);
+        }
         for latest in self.graphic_receiver.try_iter() {
-            self.dock_viewer.snapshot = latest;
+            if self.dock_viewer.hashes.last().is_some_and(|last| last.tick >= latest.date) {
+                // the simulation was reloaded
+                self.dock_viewer.hashes.clear();
+            }
+            if let Some(hash) = latest.state_hash {
+                self.dock_viewer.hashes.push(TickHash { tick: latest.date, hash });
+            }
+            let previous = std::mem::replace(&mut self.dock_viewer.snapshot, latest);
+            self.dock_viewer.previous_snapshot = Some(previous);
             let time = self.dock_viewer.snapshot.date as f64;
             let kinetic_energy = self.dock_viewer.snapshot.kinetic_energy as f64;
             let potential_energy = self.dock_viewer.snapshot.potential_energy as f64;
@@ -383,44 +701,111 @@ pub struct LogicLoop {
     simulation: GameContent,
     graphic_sender: Sender<WorldSnapshot>,
     event_receiver: Receiver<Event>,
+    status_sender: Sender<String>,
+    /// to find the solver of a saved state
+    solvers: SolverRegistry,
+    /// set when a tick failed, the simulation isn't solved anymore until the next event reloads it
+    error: Option<SolveError>,
 }
 
 impl LogicLoop {
     fn new(
         graphic_sender: Sender<WorldSnapshot>,
         event_receiver: Receiver<Event>,
+        status_sender: Sender<String>,
         tick_step: Duration,
+        solvers: SolverRegistry,
     ) -> Self {
-        let mut simulation = GameContent::empty(tick_step.as_secs_f32());
-        simulation.double();
+        // empty until the GUI sends the first scene
         Self {
-            simulation,
+            simulation: GameContent::empty(tick_step.as_secs_f32()),
             graphic_sender,
             event_receiver,
+            status_sender,
+            solvers,
+            error: None,
+        }
+    }
+}
+
+impl LogicLoop {
+    /// returns what to tell the user when the scene can't be built
+    fn reset(&mut self, settings: Settings) -> Option<String> {
+        let Settings {
+            scene,
+            solver,
+            substeps,
+            parallel_islands,
+            adaptive_stepping,
+            deterministic,
+        } = settings;
+        self.simulation.solver = solver;
+        self.simulation.set_substeps(substeps);
+        self.simulation.set_parallel_islands(parallel_islands);
+        self.simulation
+            .set_adaptive_stepping(adaptive_stepping.then(AdaptiveStepping::default));
+        self.simulation.set_deterministic(deterministic);
+        self.error = None;
+        self.simulation
+            .load_scene(&scene)
+            .err()
+            .map(|error| format!("couldn't build the scene: {error}"))
+    }
+
+    /// write the state in ``STATE_FILE``, returns what to tell the user
+    fn save_state(&mut self) -> String {
+        let text = match self.simulation.save_state() {
+            Ok(state) => state.to_ron().map_err(|error| error.to_string()),
+            Err(error) => Err(error.to_string()),
+        };
+        match text.and_then(|text| std::fs::write(STATE_FILE, text).map_err(|error| error.to_string())) {
+            Ok(()) => format!("tick {} saved to {STATE_FILE}", self.simulation.age()),
+            Err(error) => format!("couldn't save the state: {error}"),
+        }
+    }
+
+    fn load_state(&mut self, state: SavedState) -> String {
+        match self.simulation.load_state(&state, &self.solvers) {
+            Ok(()) => {
+                self.error = None;
+                format!("tick {} loaded from {STATE_FILE}", state.age)
+            }
+            Err(error) => format!("couldn't load {STATE_FILE}: {error}"),
         }
     }
 }
 
 impl GameLoop for LogicLoop {
-    fn tick(&mut self, _ctx: &GameContext) {
-        if let Ok(Event { simulation, solver }) = self.event_receiver.try_recv() {
-            self.simulation.solver = solver;
-            match simulation {
-                SimulationContent::Simple => self.simulation.simple(),
-                SimulationContent::Double => self.simulation.double(),
-                SimulationContent::Triple => self.simulation.triple(),
-                SimulationContent::Rope => self.simulation.rope(),
-                SimulationContent::Rail => self.simulation.rail(),
-                SimulationContent::Structure => self.simulation.structure(),
-                SimulationContent::Pulley => self.simulation.pulley(),
-                SimulationContent::PulleyAndRail => self.simulation.pulley_and_rail(),
-                SimulationContent::Bridge => self.simulation.bridge(),
-                SimulationContent::BridgeSoft => self.simulation.bridge_soft(),
-            };
+    fn tick(&mut self, ctx: &GameContext) {
+        if let Ok(event) = self.event_receiver.try_recv() {
+            match event {
+                Event::Reset(settings) => {
+                    if let Some(status) = self.reset(settings) {
+                        self.status_sender.send(status).unwrap();
+                    }
+                }
+                Event::SaveState => {
+                    let status = self.save_state();
+                    self.status_sender.send(status).unwrap();
+                }
+                Event::LoadState(state) => {
+                    let status = self.load_state(state);
+                    self.status_sender.send(status).unwrap();
+                }
+            }
         }
 
-        self.simulation.solve();
-        let snapshot = self.simulation.take_snapshot();
+        if self.error.is_some() {
+            // the last snapshot sent already holds the error
+            return;
+        }
+        let result = self.simulation.solve();
+        let mut snapshot = self.simulation.take_snapshot();
+        snapshot.timestamp = Some(ctx.tick_instant());
+        if let Err(error) = result {
+            self.error = Some(error.clone());
+            snapshot.error = Some(error);
+        }
         self.graphic_sender.send(snapshot).unwrap();
     }
 }
@@ -2,6 +2,7 @@ extern crate core;
 
 use crate::game_core::GameCore;
 use crate::logic_hook::LogicHook;
+use physics::solvers::SolverRegistry;
 use running_context::event_handling::EventHandler;
 use running_context::rendering::Graphic;
 use std::time::Duration;
@@ -9,14 +10,18 @@ use winit::application::ApplicationHandler;
 
 mod game_core;
 mod logic_hook;
-mod world;
 
 pub fn new_app() -> impl ApplicationHandler {
+    new_app_with_solvers(SolverRegistry::default())
+}
+
+/// same as ``new_app``, but the GUI will list the given solvers instead of the built-in ones
+pub fn new_app_with_solvers(solvers: SolverRegistry) -> impl ApplicationHandler {
     // for tracing purposes, nothing should be created before the EventHandler itself
-    EventHandler::new(|| {
+    EventHandler::new(move || {
         // Setup a bunch of state:
         let tick_duration = Duration::from_millis(8);
-        let logic = LogicHook::new(GameCore::new(tick_duration), tick_duration);
+        let logic = LogicHook::new(GameCore::new(tick_duration, solvers), tick_duration);
         let graphics = Graphic::new();
         (graphics, logic)
     })
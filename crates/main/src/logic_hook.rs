@@ -35,12 +35,11 @@ impl<T: SynchronousLoop> LogicHook<T> {
             logic.exit();
         }));
 
-        let hook = LogicHook {
+        LogicHook {
             sync_loop,
             game_thread,
             keep_running,
-        };
-        hook
+        }
     }
 }
 
@@ -76,6 +75,11 @@ impl GameContext {
         self.next_tick = Instant::now();
     }
 
+    /// when the current tick was due, the ticks are ``tick_length`` apart even when one of them is late
+    pub fn tick_instant(&self) -> Instant {
+        self.next_tick
+    }
+
     fn wait_until_next_tick(&mut self) -> bool {
         if self.keep_running.load(Ordering::Acquire) {
             self.next_tick += self.tick_length;
@@ -0,0 +1,90 @@
+//! Print the error of the derivatives of every built-in constraint against finite differences of C.
+//! ``cargo run -p physics --example check_derivatives``
+
+use nalgebra::Vector2;
+use physics::constraints::ConstraintDescription;
+use physics::verification::DerivativeBench;
+
+const SAMPLES: usize = 1000;
+const SEED: u64 = 42;
+
+fn main() {
+    let local_a = Vector2::new(0.3, -0.2);
+    let local_b = Vector2::new(-0.1, 0.4);
+    let anchor = Vector2::new(0.5, 1.0);
+    let axis = Vector2::new(0.6, 0.8);
+
+    let constraints = [
+        (
+            "Distance",
+            ConstraintDescription::Distance {
+                body_a: 0,
+                local_a,
+                body_b: 1,
+                local_b,
+                distance: 1.0,
+            },
+        ),
+        (
+            "Anchor",
+            ConstraintDescription::Anchor {
+                body: 0,
+                local: local_a,
+                anchor,
+                distance: 1.0,
+            },
+        ),
+        (
+            "Plane",
+            ConstraintDescription::Plane {
+                body: 0,
+                normal: axis,
+                origin: 1.1,
+            },
+        ),
+        (
+            "Pulley",
+            ConstraintDescription::Pulley {
+                body_a: 0,
+                body_b: 1,
+                anchor_a: anchor,
+                anchor_b: -anchor,
+                distance: 3.0,
+            },
+        ),
+        (
+            "Hinge",
+            ConstraintDescription::Hinge {
+                body_a: 0,
+                local_a,
+                body_b: 1,
+                local_b,
+                axis,
+            },
+        ),
+        (
+            "Pin",
+            ConstraintDescription::Pin {
+                body: 0,
+                local: local_a,
+                anchor,
+                axis,
+            },
+        ),
+        (
+            "Gear",
+            ConstraintDescription::Gear {
+                body_a: 0,
+                body_b: 1,
+                ratio: 0.5,
+                phase: 0.3,
+            },
+        ),
+    ];
+    for (name, description) in constraints {
+        let bodies = description.bodies().into_iter().max().map_or(0, |last| last + 1);
+        let mut bench = DerivativeBench::new(bodies);
+        let expression = description.build::<f64>(bench.bodies()).unwrap();
+        println!("{name:10} {}", bench.check(expression.as_ref(), SAMPLES, SEED));
+    }
+}
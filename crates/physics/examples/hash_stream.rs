@@ -0,0 +1,79 @@
+//! Run a scene headless in deterministic mode and print its hash stream, or find where two streams diverge.
+//! The scene is run like the GUI runs it: f32, ticks of 8 ms, 4 substeps.
+//! ``cargo run -p physics --example hash_stream -- scenes/double.ron HybridV3 1000 > desktop.txt``
+//! ``cargo run -p physics --example hash_stream -- diff desktop.txt android.txt``
+
+use physics::GameContent;
+use physics::determinism::{first_divergence, read_hash_stream, write_hash_stream};
+use physics::scene::Scene;
+use physics::solvers::SolverRegistry;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: hash_stream <scene file> <solver> [ticks] | hash_stream diff <a> <b>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["diff", a, b] => diff(a, b),
+        [scene, solver] => run(scene, solver, 1000),
+        [scene, solver, ticks] => match ticks.parse() {
+            Ok(ticks) => run(scene, solver, ticks),
+            Err(_) => fail(USAGE),
+        },
+        _ => fail(USAGE),
+    }
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("{message}");
+    ExitCode::FAILURE
+}
+
+fn run(path: &str, solver: &str, ticks: u32) -> ExitCode {
+    let mut simulation = GameContent::empty(0.008);
+    simulation.solver = match SolverRegistry::default().get(solver) {
+        Some(solver) => solver,
+        None => return fail(&format!("unknown solver {solver}")),
+    };
+    simulation.set_deterministic(true);
+    let scene = match std::fs::read_to_string(path) {
+        Ok(text) => Scene::from_ron(&text).map_err(|error| error.to_string()),
+        Err(error) => Err(error.to_string()),
+    };
+    if let Err(error) = scene.and_then(|scene| simulation.load_scene(&scene).map_err(|error| error.to_string())) {
+        return fail(&format!("couldn't load {path}: {error}"));
+    }
+    for _ in 0..ticks {
+        let result = simulation.solve();
+        simulation.take_snapshot();
+        if let Err(error) = result {
+            eprintln!("stopped at tick {}: {error}", simulation.age());
+            break;
+        }
+    }
+    let header = format!("{path} {solver} f32");
+    match write_hash_stream(&header, simulation.hash_stream(), std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => fail(&error.to_string()),
+    }
+}
+
+fn diff(a: &str, b: &str) -> ExitCode {
+    let read = |path: &str| File::open(path).and_then(|file| read_hash_stream(BufReader::new(file)));
+    let (a, b) = match (read(a), read(b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(error), _) | (_, Err(error)) => return fail(&error.to_string()),
+    };
+    match first_divergence(&a, &b) {
+        Some(tick) => {
+            println!("first divergence at tick {tick}");
+            ExitCode::FAILURE
+        }
+        None => {
+            println!("identical over {} ticks", a.len().min(b.len()));
+            ExitCode::SUCCESS
+        }
+    }
+}
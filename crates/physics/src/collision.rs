@@ -0,0 +1,153 @@
+//! Collision detection between circle colliders and static walls.
+//! Contacts are not resolved here, they are turned into unilateral constraints by ``GameContent``
+//! and go through the same J matrix as every other constraint.
+
+use crate::components::{Collider, Position, Velocity};
+use crate::scalar::{Real, real, to_f64};
+use hecs::{Entity, World};
+use nalgebra::Vector2;
+use std::collections::HashMap;
+
+/// static half plane, bodies stay on the side pointed by the normal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wall<T: Real> {
+    pub normal: Vector2<T>,
+    /// distance of the wall from the origin along the normal
+    pub origin: T,
+}
+
+impl<T: Real> Wall<T> {
+    pub fn new(normal: Vector2<T>, origin: Vector2<T>) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            origin: normal.dot(&origin),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Contact<T: Real> {
+    /// two circles, ``distance`` is the sum of the radii
+    Bodies {
+        body_a: Entity,
+        body_b: Entity,
+        distance: T,
+    },
+    /// a circle against a wall, ``origin`` is already offset by the radius
+    Wall {
+        body: Entity,
+        normal: Vector2<T>,
+        origin: T,
+    },
+}
+
+/// Broadphase, every collider is stored in the cells its bounding box overlaps,
+/// only colliders sharing a cell are tested against each other.
+pub struct SpatialHash<T: Real> {
+    cell_size: T,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl<T: Real> SpatialHash<T> {
+    pub fn new(cell_size: T) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: Vector2<T>) -> (i32, i32) {
+        (
+            to_f64((point.x / self.cell_size).floor()) as i32,
+            to_f64((point.y / self.cell_size).floor()) as i32,
+        )
+    }
+
+    pub fn insert(&mut self, index: usize, center: Vector2<T>, radius: T) {
+        let (min_x, min_y) = self.cell(center - Vector2::repeat(radius));
+        let (max_x, max_y) = self.cell(center + Vector2::repeat(radius));
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// every pair of indices sharing at least one cell, each pair is only returned once with the smallest index first
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<_> = self
+            .cells
+            .values()
+            .flat_map(|cell| {
+                cell.iter().enumerate().flat_map(move |(i, &a)| {
+                    cell[i + 1..].iter().map(move |&b| (a.min(b), a.max(b)))
+                })
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
+
+/// Find every contact that may happen during the next step.
+/// Contacts are speculative: a pair is reported as soon as the gap could be closed within ``time_step``,
+/// the unilateral constraint leaves it alone as long as it doesn't close.
+pub fn detect_contacts<T: Real>(world: &World, walls: &[Wall<T>], time_step: T) -> Vec<Contact<T>> {
+    let mut query = world.query::<(&Position<T>, &Velocity<T>, &Collider<T>)>();
+    let colliders: Vec<_> = query
+        .iter()
+        .map(|(entity, (pos, velocity, collider))| {
+            // how far the collider may travel during the step
+            let margin = velocity.norm() * time_step;
+            (entity, pos.actual, collider.radius, margin)
+        })
+        .collect();
+
+    let mut contacts = Vec::new();
+
+    for &(body, pos, radius, margin) in colliders.iter() {
+        for wall in walls {
+            let gap = wall.normal.dot(&pos) - wall.origin - radius;
+            if gap < margin {
+                contacts.push(Contact::Wall {
+                    body,
+                    normal: wall.normal,
+                    origin: wall.origin + radius,
+                });
+            }
+        }
+    }
+
+    let Some(cell_size) = colliders
+        .iter()
+        .map(|&(_, _, radius, margin)| (radius + margin) * real(2.0))
+        .reduce(T::max)
+    else {
+        return contacts;
+    };
+    let mut hash = SpatialHash::new(cell_size);
+    for (i, &(_, pos, radius, margin)) in colliders.iter().enumerate() {
+        hash.insert(i, pos, radius + margin);
+    }
+
+    for (a, b) in hash.candidate_pairs() {
+        let (body_a, pos_a, radius_a, margin_a) = colliders[a];
+        let (body_b, pos_b, radius_b, margin_b) = colliders[b];
+        let center_distance = (pos_a - pos_b).norm();
+        // concentric circles have no contact normal
+        if center_distance == T::zero() {
+            continue;
+        }
+        let distance = radius_a + radius_b;
+        if center_distance - distance < margin_a + margin_b {
+            contacts.push(Contact::Bodies {
+                body_a,
+                body_b,
+                distance,
+            });
+        }
+    }
+    contacts
+}
@@ -0,0 +1,270 @@
+use crate::constraints::Coordinates;
+use crate::dual::{Differentiable, Dual, lift};
+use crate::scalar::{Real, real, to_f64};
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position<T: Real> {
+    pub actual: Vector2<T>,
+    pub last_tick: Vector2<T>,
+}
+
+impl<T: Real> Deref for Position<T> {
+    type Target = Vector2<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.actual
+    }
+}
+
+impl<T: Real> DerefMut for Position<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.actual
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Velocity<T: Real>(pub Vector2<T>);
+
+impl<T: Real> Deref for Velocity<T> {
+    type Target = Vector2<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Real> DerefMut for Velocity<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Acceleration<T: Real>(pub Vector2<T>);
+
+impl<T: Real> Deref for Acceleration<T> {
+    type Target = Vector2<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Real> DerefMut for Acceleration<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mass<T: Real> {
+    pub mass: T,
+    pub inv_mass: T,
+}
+
+impl<T: Real> Mass<T> {
+    pub fn new(mass: T) -> Self {
+        Self {
+            mass,
+            inv_mass: mass.recip(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubjectToPhysic(pub usize);
+
+/// orientation of the body in radian, it follows the same convention as ``Position``
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation<T: Real> {
+    pub actual: T,
+    pub last_tick: T,
+}
+
+impl<T: Real> Deref for Rotation<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.actual
+    }
+}
+
+impl<T: Real> DerefMut for Rotation<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.actual
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AngularVelocity<T: Real>(pub T);
+
+impl<T: Real> Deref for AngularVelocity<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Real> DerefMut for AngularVelocity<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// moment of inertia around the center of mass
+/// point masses have an ``inv_inertia`` of 0, constraints can't make them spin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inertia<T: Real> {
+    pub inertia: T,
+    pub inv_inertia: T,
+}
+
+/// circle shape of a body, only bodies with a collider collide
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collider<T: Real> {
+    pub radius: T,
+}
+
+/// How a body moves, every body has one.
+/// Static and kinematic bodies have an infinite mass: gravity and constraints don't move them,
+/// but any constraint can attach to them like to a dynamic body
+#[derive(Clone)]
+pub enum BodyKind<T: Real> {
+    /// moved by gravity and constraints
+    Dynamic,
+    /// never moves
+    Static,
+    /// follows a ``Trajectory``, the state is given for a time since the scene was built.
+    /// The description is ``None`` for the trajectories that can't be saved
+    Kinematic(Arc<dyn Fn(T) -> KinematicState<T> + Send + Sync>, Option<TrajectoryDescription>),
+}
+
+impl<T: Real> BodyKind<T> {
+    pub fn kinematic(trajectory: impl Trajectory<T> + 'static) -> Self {
+        let description = trajectory.description();
+        let state = Arc::new(move |time| {
+            // one evaluation with the time along itself, twice, gives the velocity and the acceleration
+            let time = Dual::new(Dual::new(time, T::one()), Dual::new(T::one(), T::zero()));
+            let coordinates = trajectory.coordinates(time);
+            let part = |value: Dual<Dual<T>>| (value.re.re, value.re.eps, value.eps.eps);
+            let (x, velocity_x, acceleration_x) = part(coordinates.position.x);
+            let (y, velocity_y, acceleration_y) = part(coordinates.position.y);
+            let (angle, angular_velocity, angular_acceleration) = part(coordinates.angle);
+            KinematicState {
+                coordinates: Coordinates::new(Vector2::new(x, y), angle),
+                velocity: Coordinates::new(Vector2::new(velocity_x, velocity_y), angular_velocity),
+                acceleration: Coordinates::new(Vector2::new(acceleration_x, acceleration_y), angular_acceleration),
+            }
+        });
+        BodyKind::Kinematic(state, description)
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        matches!(self, BodyKind::Dynamic)
+    }
+
+    /// the state at ``time`` of a kinematic body, ``None`` for the other kinds
+    pub fn state(&self, time: T) -> Option<KinematicState<T>> {
+        match self {
+            BodyKind::Kinematic(trajectory, _) => Some(trajectory(time)),
+            _ => None,
+        }
+    }
+
+    /// ``None`` for a kinematic body following a trajectory without description
+    pub fn description(&self) -> Option<BodyKindDescription> {
+        match self {
+            BodyKind::Dynamic => Some(BodyKindDescription::Dynamic),
+            BodyKind::Static => Some(BodyKindDescription::Static),
+            BodyKind::Kinematic(_, trajectory) => trajectory.map(BodyKindDescription::Kinematic),
+        }
+    }
+}
+
+/// A ``BodyKind`` in a form that can be saved or written in a scene file
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BodyKindDescription {
+    #[default]
+    Dynamic,
+    Static,
+    Kinematic(TrajectoryDescription),
+}
+
+impl BodyKindDescription {
+    pub fn body_kind<T: Real>(&self) -> BodyKind<T> {
+        match self {
+            BodyKindDescription::Dynamic => BodyKind::Dynamic,
+            BodyKindDescription::Static => BodyKind::Static,
+            BodyKindDescription::Kinematic(trajectory) => trajectory.body_kind(),
+        }
+    }
+}
+
+/// position, velocity and acceleration of a kinematic body at a given time
+#[derive(Debug, Clone, Copy)]
+pub struct KinematicState<T> {
+    pub coordinates: Coordinates<T>,
+    pub velocity: Coordinates<T>,
+    pub acceleration: Coordinates<T>,
+}
+
+/// Motion of a kinematic body: its position and angle as functions of time.
+/// Like a ``ConstraintFunction`` it is written once for any ``Differentiable`` scalar,
+/// the velocity and the acceleration are derived from it
+pub trait Trajectory<T: Real>: Send + Sync {
+    fn coordinates<S: Differentiable<T>>(&self, time: S) -> Coordinates<S>;
+    /// how to build the trajectory again, a kinematic body following a trajectory without one can't be saved
+    fn description(&self) -> Option<TrajectoryDescription> {
+        None
+    }
+}
+
+/// The built-in trajectories, in a form that can be saved, see ``crate::persistence``
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrajectoryDescription {
+    Oscillation {
+        center: Vector2<f64>,
+        amplitude: Vector2<f64>,
+        pulsation: f64,
+    },
+}
+
+impl TrajectoryDescription {
+    pub fn body_kind<T: Real>(&self) -> BodyKind<T> {
+        match *self {
+            TrajectoryDescription::Oscillation {
+                center,
+                amplitude,
+                pulsation,
+            } => BodyKind::kinematic(Oscillation {
+                center: center.map(real),
+                amplitude: amplitude.map(real),
+                pulsation: real(pulsation),
+            }),
+        }
+    }
+}
+
+/// back and forth along ``amplitude`` around ``center``, ``pulsation`` in radian per second
+#[derive(Debug, Clone, Copy)]
+pub struct Oscillation<T: Real> {
+    pub center: Vector2<T>,
+    pub amplitude: Vector2<T>,
+    pub pulsation: T,
+}
+
+impl<T: Real> Trajectory<T> for Oscillation<T> {
+    fn coordinates<S: Differentiable<T>>(&self, time: S) -> Coordinates<S> {
+        let offset = (time * S::constant(self.pulsation)).sin();
+        let position = lift(self.center) + lift::<T, S>(self.amplitude) * offset;
+        Coordinates::new(position, S::zero())
+    }
+
+    fn description(&self) -> Option<TrajectoryDescription> {
+        Some(TrajectoryDescription::Oscillation {
+            center: self.center.map(to_f64),
+            amplitude: self.amplitude.map(to_f64),
+            pulsation: to_f64(self.pulsation),
+        })
+    }
+}
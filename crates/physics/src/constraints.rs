@@ -0,0 +1,748 @@
+use crate::components::{
+    Acceleration, AngularVelocity, Position, Rotation, SubjectToPhysic, Velocity,
+};
+use hecs::{Entity, View};
+use crate::dual::{Differentiable, Dual, lift, norm, rotate};
+use crate::scalar::{Real, real, to_f32, to_f64, vector_to_f32};
+use nalgebra::Vector2;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+
+/// number of degrees of freedom of a body: x, y and the angle
+pub const DOF: usize = 3;
+
+/// what the velocity level expressions read from the bodies
+pub type VelocityQuery<'a, T> = (
+    &'a Position<T>,
+    &'a Rotation<T>,
+    &'a Velocity<T>,
+    &'a AngularVelocity<T>,
+);
+/// same as ``VelocityQuery`` with the accelerations, for the third order term
+pub type AccelerationQuery<'a, T> = (
+    &'a Position<T>,
+    &'a Rotation<T>,
+    &'a Velocity<T>,
+    &'a AngularVelocity<T>,
+    &'a Acceleration<T>,
+);
+
+pub enum ConstraintWidget {
+    None,
+    /// two bodies and the local offsets the link is attached to
+    Link(usize, Vector2<f32>, usize, Vector2<f32>),
+    /// a body, the local offset and the anchor in world space
+    Anchor(usize, Vector2<f32>, Vector2<f32>),
+    Plane(Vector2<f32>, f32),
+    Pulley(usize, usize, Vector2<f32>, Vector2<f32>),
+    /// same as ``Link``, but the attachment points are held together
+    Hinge(usize, Vector2<f32>, usize, Vector2<f32>),
+    /// same as ``Anchor``, but the attachment point is held on the anchor
+    Pin(usize, Vector2<f32>, Vector2<f32>),
+    Gear(usize, usize),
+}
+
+/// Non zero entries of one row of the jacobian.
+/// It is indexed like a dense row, columns that are never written stay 0
+#[derive(Debug, Clone)]
+pub struct JacobianRow<T: Real> {
+    entries: Vec<(usize, T)>,
+    /// returned by reference for the columns that were never written
+    zero: T,
+}
+
+impl<T: Real> Default for JacobianRow<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            zero: T::zero(),
+        }
+    }
+}
+
+impl<T: Real> JacobianRow<T> {
+    /// ``(column, value)`` of every written entry
+    pub fn entries(&self) -> &[(usize, T)] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<T: Real> Index<usize> for JacobianRow<T> {
+    type Output = T;
+    fn index(&self, column: usize) -> &T {
+        self.entries
+            .iter()
+            .find(|(c, _)| *c == column)
+            .map(|(_, value)| value)
+            .unwrap_or(&self.zero)
+    }
+}
+
+impl<T: Real> IndexMut<usize> for JacobianRow<T> {
+    fn index_mut(&mut self, column: usize) -> &mut T {
+        let position = match self.entries.iter().position(|(c, _)| *c == column) {
+            Some(position) => position,
+            None => {
+                self.entries.push((column, T::zero()));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[position].1
+    }
+}
+
+pub trait ConstraintExpression<T: Real>: Send + Sync {
+    fn build_j_row(
+        &self,
+        bodies: &View<(&Position<T>, &Rotation<T>, &SubjectToPhysic)>,
+        row_view: &mut JacobianRow<T>,
+    );
+    fn compute_j_dot_q_dot(
+        &self,
+        _bodies: &View<VelocityQuery<T>>,
+    ) -> T {
+        T::zero()
+    }
+    fn evaluate_c_dot(
+        &self,
+        _bodies: &View<VelocityQuery<T>>,
+    ) -> T {
+        T::zero()
+    }
+    fn evaluate_c(&self, _bodies: &View<(&Position<T>, &Rotation<T>)>) -> T {
+        T::zero()
+    }
+    fn compute_ddot_q_dot_plus_j_dot_q_ddot(
+        &self,
+        _bodies: &View<AccelerationQuery<T>>,
+    ) -> T {
+        T::zero()
+    }
+    // TODO: find a better way to do this
+    fn widget(&self, _convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::None
+    }
+    /// the bodies the constraint depends on, every one of them must be simulated for the constraint to be solved
+    fn bodies(&self) -> Vec<Entity> {
+        Vec::new()
+    }
+    /// how to build the constraint again, a world holding a constraint without one can't be saved
+    fn description(&self, _convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        None
+    }
+}
+
+/// Coordinates of one body, or their velocity.
+/// ``S`` is the scalar the ``ConstraintFunction`` is evaluated with
+#[derive(Debug, Clone, Copy)]
+pub struct Coordinates<S> {
+    pub position: Vector2<S>,
+    pub angle: S,
+}
+
+impl<S: Copy> Coordinates<S> {
+    /// world position of a point attached to the body at the local offset ``local``
+    pub fn point<T: Real>(&self, local: Vector2<T>) -> Vector2<S>
+    where
+        S: Differentiable<T>,
+    {
+        self.position + rotate(lift(local), self.angle)
+    }
+
+    pub fn new(position: Vector2<S>, angle: S) -> Self {
+        Self { position, angle }
+    }
+}
+
+/// the coordinates moving along ``direction``, the ``eps`` part of each coordinate is the matching one of ``direction``
+fn along<S: nalgebra::Scalar + Copy>(coordinates: &[Coordinates<S>], direction: &[Coordinates<S>]) -> Vec<Coordinates<Dual<S>>> {
+    coordinates
+        .iter()
+        .zip(direction)
+        .map(|(q, d)| {
+            Coordinates::new(
+                Vector2::new(Dual::new(q.position.x, d.position.x), Dual::new(q.position.y, d.position.y)),
+                Dual::new(q.angle, d.angle),
+            )
+        })
+        .collect()
+}
+
+/// the coordinates with a derivative of 0
+fn still<S: nalgebra::Scalar + Copy + Zero>(coordinates: &[Coordinates<S>]) -> Vec<Coordinates<Dual<S>>> {
+    let zero = Coordinates::new(Vector2::new(S::zero(), S::zero()), S::zero());
+    along(coordinates, &vec![zero; coordinates.len()])
+}
+
+/// A constraint written as C(q) only. The jacobian and its time derivatives are obtained by differentiating
+/// ``evaluate`` with dual numbers (see ``crate::dual``), so they can't disagree with it.
+/// Every ``ConstraintFunction`` is a ``ConstraintExpression``
+pub trait ConstraintFunction<T: Real>: Send + Sync {
+    /// the bodies the constraint depends on, ``evaluate`` is given their coordinates in the same order
+    fn bodies(&self) -> Vec<Entity>;
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S;
+    fn widget(&self, _convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::None
+    }
+    fn description(&self, _convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        None
+    }
+}
+
+/// coordinates and velocities of the bodies of a constraint
+type VelocityState<T> = (Vec<Coordinates<T>>, Vec<Coordinates<T>>);
+
+/// coordinates and velocities of the bodies of ``function``, ``None`` if one of them is missing
+fn velocity_state<T: Real>(
+    function: &impl ConstraintFunction<T>,
+    bodies: &View<VelocityQuery<T>>,
+) -> Option<VelocityState<T>> {
+    ConstraintFunction::bodies(function)
+        .into_iter()
+        .map(|e| {
+            bodies.get(e).map(|(pos, rot, vel, ang_vel)| {
+                (Coordinates::new(pos.actual, rot.actual), Coordinates::new(vel.0, ang_vel.0))
+            })
+        })
+        .collect()
+}
+
+impl<T: Real, F: ConstraintFunction<T>> ConstraintExpression<T> for F {
+    /// one evaluation per coordinate, the columns the constraint doesn't depend on are not written
+    fn build_j_row(
+        &self,
+        bodies: &View<(&Position<T>, &Rotation<T>, &SubjectToPhysic)>,
+        row_view: &mut JacobianRow<T>,
+    ) {
+        let Some(states) = ConstraintFunction::bodies(self)
+            .into_iter()
+            .map(|e| bodies.get(e))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+        let q: Vec<_> = states
+            .iter()
+            .map(|(pos, rot, _)| Coordinates::new(pos.actual, rot.actual))
+            .collect();
+
+        let zero = Coordinates::new(Vector2::zeros(), T::zero());
+        let mut direction = vec![zero; q.len()];
+        for (k, (_, _, SubjectToPhysic(index))) in states.iter().enumerate() {
+            for coordinate in 0..DOF {
+                match coordinate {
+                    0 => direction[k].position.x = T::one(),
+                    1 => direction[k].position.y = T::one(),
+                    _ => direction[k].angle = T::one(),
+                }
+                let derivative = self.evaluate(&along(&q, &direction)).eps;
+                direction[k] = zero;
+                if derivative != T::zero() {
+                    row_view[index * DOF + coordinate] = derivative;
+                }
+            }
+        }
+    }
+
+    /// second derivative of C along q_dot
+    fn compute_j_dot_q_dot(
+        &self,
+        bodies: &View<VelocityQuery<T>>,
+    ) -> T {
+        let Some((q, q_dot)) = velocity_state(self, bodies) else {
+            return T::zero();
+        };
+        self.evaluate(&along(&along(&q, &q_dot), &still(&q_dot))).eps.eps
+    }
+
+    fn evaluate_c_dot(
+        &self,
+        bodies: &View<VelocityQuery<T>>,
+    ) -> T {
+        let Some((q, q_dot)) = velocity_state(self, bodies) else {
+            return T::zero();
+        };
+        self.evaluate(&along(&q, &q_dot)).eps
+    }
+
+    fn evaluate_c(&self, bodies: &View<(&Position<T>, &Rotation<T>)>) -> T {
+        let Some(q) = ConstraintFunction::bodies(self)
+            .into_iter()
+            .map(|e| bodies.get(e).map(|(pos, rot)| Coordinates::new(pos.actual, rot.actual)))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return T::zero();
+        };
+        self.evaluate(&q)
+    }
+
+    /// time derivative of ``compute_j_dot_q_dot``: the second derivative of C along q_dot is taken while
+    /// q moves along q_dot and q_dot along q_ddot. Only the linear acceleration is known, the angular one is taken as 0
+    fn compute_ddot_q_dot_plus_j_dot_q_ddot(
+        &self,
+        bodies: &View<AccelerationQuery<T>>,
+    ) -> T {
+        let Some(states) = ConstraintFunction::bodies(self)
+            .into_iter()
+            .map(|e| bodies.get(e))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return T::zero();
+        };
+        let (q, (q_dot, q_ddot)): (Vec<_>, (Vec<_>, Vec<_>)) = states
+            .iter()
+            .map(|(pos, rot, vel, ang_vel, accel)| {
+                (
+                    Coordinates::new(pos.actual, rot.actual),
+                    (Coordinates::new(vel.0, ang_vel.0), Coordinates::new(accel.0, T::zero())),
+                )
+            })
+            .unzip();
+
+        let moving_q_dot = along(&q_dot, &q_ddot);
+        let second_order = along(&along(&q, &q_dot), &moving_q_dot);
+        self.evaluate(&along(&second_order, &still(&moving_q_dot))).eps.eps.eps
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintFunction::widget(self, convertor)
+    }
+
+    fn bodies(&self) -> Vec<Entity> {
+        ConstraintFunction::bodies(self)
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        ConstraintFunction::description(self, convertor)
+    }
+}
+
+pub struct DistanceConstraint<T: Real> {
+    pub body_a: Entity,
+    pub local_a: Vector2<T>,
+    pub body_b: Entity,
+    pub local_b: Vector2<T>,
+    pub distance: T,
+}
+
+impl<T: Real> ConstraintFunction<T> for DistanceConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body_a, self.body_b]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        norm(bodies[0].point(self.local_a) - bodies[1].point(self.local_b)) - S::constant(self.distance)
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Link(
+            convertor(self.body_a),
+            vector_to_f32(self.local_a),
+            convertor(self.body_b),
+            vector_to_f32(self.local_b),
+        )
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Distance {
+            body_a: convertor(self.body_a),
+            local_a: self.local_a.map(to_f64),
+            body_b: convertor(self.body_b),
+            local_b: self.local_b.map(to_f64),
+            distance: to_f64(self.distance),
+        })
+    }
+}
+
+pub struct AnchorConstraint<T: Real> {
+    pub body: Entity,
+    pub local: Vector2<T>,
+    pub anchor: Vector2<T>,
+    pub distance: T,
+}
+
+impl<T: Real> ConstraintFunction<T> for AnchorConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        norm(bodies[0].point(self.local) - lift(self.anchor)) - S::constant(self.distance)
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Anchor(
+            convertor(self.body),
+            vector_to_f32(self.local),
+            vector_to_f32(self.anchor),
+        )
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Anchor {
+            body: convertor(self.body),
+            local: self.local.map(to_f64),
+            anchor: self.anchor.map(to_f64),
+            distance: to_f64(self.distance),
+        })
+    }
+}
+
+/// Keep the body collinear with the director
+pub struct PlaneConstraint<T: Real> {
+    pub body: Entity,
+    pub normal: Vector2<T>,
+    origin: T,
+}
+
+impl<T: Real> PlaneConstraint<T> {
+    pub fn new(body: Entity, normal: Vector2<T>, origin: Vector2<T>) -> Self {
+        let normal = normal.normalize();
+        Self {
+            body,
+            normal,
+            origin: normal.dot(&origin),
+        }
+    }
+}
+
+impl<T: Real> ConstraintFunction<T> for PlaneConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        bodies[0].position.dot(&lift(self.normal)) - S::constant(self.origin)
+    }
+
+    fn widget(&self, _convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Plane(vector_to_f32(self.normal), -to_f32(self.origin))
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Plane {
+            body: convertor(self.body),
+            normal: self.normal.map(to_f64),
+            origin: to_f64(self.origin),
+        })
+    }
+}
+
+pub struct PulleyConstraint<T: Real> {
+    pub body_a: Entity,
+    pub body_b: Entity,
+    pub anchor_a: Vector2<T>,
+    pub anchor_b: Vector2<T>,
+    pub distance: T,
+}
+
+impl<T: Real> ConstraintFunction<T> for PulleyConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body_a, self.body_b]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        norm(bodies[0].position - lift(self.anchor_a)) + norm(bodies[1].position - lift(self.anchor_b))
+            - S::constant(self.distance)
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Pulley(
+            convertor(self.body_a),
+            convertor(self.body_b),
+            vector_to_f32(self.anchor_a),
+            vector_to_f32(self.anchor_b),
+        )
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Pulley {
+            body_a: convertor(self.body_a),
+            body_b: convertor(self.body_b),
+            anchor_a: self.anchor_a.map(to_f64),
+            anchor_b: self.anchor_b.map(to_f64),
+            distance: to_f64(self.distance),
+        })
+    }
+}
+
+/// Keep two attachment points together along ``axis``,
+/// a hinge between two bodies is made of two of them with orthogonal axes
+pub struct HingeConstraint<T: Real> {
+    pub body_a: Entity,
+    pub local_a: Vector2<T>,
+    pub body_b: Entity,
+    pub local_b: Vector2<T>,
+    pub axis: Vector2<T>,
+}
+
+impl<T: Real> ConstraintFunction<T> for HingeConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body_a, self.body_b]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        lift(self.axis).dot(&(bodies[0].point(self.local_a) - bodies[1].point(self.local_b)))
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Hinge(
+            convertor(self.body_a),
+            vector_to_f32(self.local_a),
+            convertor(self.body_b),
+            vector_to_f32(self.local_b),
+        )
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Hinge {
+            body_a: convertor(self.body_a),
+            local_a: self.local_a.map(to_f64),
+            body_b: convertor(self.body_b),
+            local_b: self.local_b.map(to_f64),
+            axis: self.axis.map(to_f64),
+        })
+    }
+}
+
+/// Keep an attachment point on a fixed anchor along ``axis``, this is the ``HingeConstraint`` equivalent of ``AnchorConstraint``
+pub struct PinConstraint<T: Real> {
+    pub body: Entity,
+    pub local: Vector2<T>,
+    pub anchor: Vector2<T>,
+    pub axis: Vector2<T>,
+}
+
+impl<T: Real> ConstraintFunction<T> for PinConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        lift(self.axis).dot(&(bodies[0].point(self.local) - lift(self.anchor)))
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Pin(
+            convertor(self.body),
+            vector_to_f32(self.local),
+            vector_to_f32(self.anchor),
+        )
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Pin {
+            body: convertor(self.body),
+            local: self.local.map(to_f64),
+            anchor: self.anchor.map(to_f64),
+            axis: self.axis.map(to_f64),
+        })
+    }
+}
+
+/// Couple the rotation of two bodies: ``angle_a + ratio * angle_b`` stays constant.
+/// For two meshed gears, ``ratio`` is radius_b / radius_a
+pub struct GearConstraint<T: Real> {
+    pub body_a: Entity,
+    pub body_b: Entity,
+    pub ratio: T,
+    /// value of ``angle_a + ratio * angle_b`` when the gears were meshed
+    pub phase: T,
+}
+
+impl<T: Real> ConstraintFunction<T> for GearConstraint<T> {
+    fn bodies(&self) -> Vec<Entity> {
+        vec![self.body_a, self.body_b]
+    }
+
+    fn evaluate<S: Differentiable<T>>(&self, bodies: &[Coordinates<S>]) -> S {
+        bodies[0].angle + S::constant(self.ratio) * bodies[1].angle - S::constant(self.phase)
+    }
+
+    fn widget(&self, convertor: &dyn Fn(Entity) -> usize) -> ConstraintWidget {
+        ConstraintWidget::Gear(convertor(self.body_a), convertor(self.body_b))
+    }
+
+    fn description(&self, convertor: &dyn Fn(Entity) -> usize) -> Option<ConstraintDescription> {
+        Some(ConstraintDescription::Gear {
+            body_a: convertor(self.body_a),
+            body_b: convertor(self.body_b),
+            ratio: to_f64(self.ratio),
+            phase: to_f64(self.phase),
+        })
+    }
+}
+
+/// The built-in constraints in a form that can be saved, the bodies are ``SubjectToPhysic`` indices
+/// and the values are in f64 whatever the scalar of the simulation, see ``crate::persistence``.
+/// The attachment points default to the center of the bodies
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ConstraintDescription {
+    Distance {
+        body_a: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local_a: Vector2<f64>,
+        body_b: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local_b: Vector2<f64>,
+        distance: f64,
+    },
+    Anchor {
+        body: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local: Vector2<f64>,
+        anchor: Vector2<f64>,
+        distance: f64,
+    },
+    /// the plane is normal . position = origin, ``normal`` doesn't need to be normalised
+    Plane {
+        body: usize,
+        normal: Vector2<f64>,
+        origin: f64,
+    },
+    Pulley {
+        body_a: usize,
+        body_b: usize,
+        anchor_a: Vector2<f64>,
+        anchor_b: Vector2<f64>,
+        distance: f64,
+    },
+    Hinge {
+        body_a: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local_a: Vector2<f64>,
+        body_b: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local_b: Vector2<f64>,
+        axis: Vector2<f64>,
+    },
+    Pin {
+        body: usize,
+        #[serde(default, skip_serializing_if = "is_origin")]
+        local: Vector2<f64>,
+        anchor: Vector2<f64>,
+        axis: Vector2<f64>,
+    },
+    Gear {
+        body_a: usize,
+        body_b: usize,
+        ratio: f64,
+        phase: f64,
+    },
+}
+
+impl ConstraintDescription {
+    /// ``SubjectToPhysic`` index of the bodies of the constraint
+    pub fn bodies(&self) -> Vec<usize> {
+        match *self {
+            ConstraintDescription::Anchor { body, .. }
+            | ConstraintDescription::Plane { body, .. }
+            | ConstraintDescription::Pin { body, .. } => vec![body],
+            ConstraintDescription::Distance { body_a, body_b, .. }
+            | ConstraintDescription::Pulley { body_a, body_b, .. }
+            | ConstraintDescription::Hinge { body_a, body_b, .. }
+            | ConstraintDescription::Gear { body_a, body_b, .. } => vec![body_a, body_b],
+        }
+    }
+
+    /// the constraint itself, ``entities`` gives the entity of each index. ``None`` if an index is out of it
+    pub fn build<T: Real>(&self, entities: &[Entity]) -> Option<Box<dyn ConstraintExpression<T>>> {
+        let entity = |index: usize| entities.get(index).copied();
+        let vector = |value: Vector2<f64>| value.map(real::<T>);
+        Some(match *self {
+            ConstraintDescription::Distance {
+                body_a,
+                local_a,
+                body_b,
+                local_b,
+                distance,
+            } => Box::new(DistanceConstraint {
+                body_a: entity(body_a)?,
+                local_a: vector(local_a),
+                body_b: entity(body_b)?,
+                local_b: vector(local_b),
+                distance: real(distance),
+            }),
+            ConstraintDescription::Anchor {
+                body,
+                local,
+                anchor,
+                distance,
+            } => Box::new(AnchorConstraint {
+                body: entity(body)?,
+                local: vector(local),
+                anchor: vector(anchor),
+                distance: real(distance),
+            }),
+            ConstraintDescription::Plane { body, normal, origin } => {
+                // the normal of a saved simulation is normalised already, dividing it by a length rounded to
+                // 1 +- epsilon would change its last bit and the state wouldn't be restored exactly
+                let normal = vector(normal);
+                let length = normal.norm();
+                let length = if (length - T::one()).abs() < T::default_epsilon() * real(4.0) { T::one() } else { length };
+                Box::new(PlaneConstraint {
+                    body: entity(body)?,
+                    normal: normal / length,
+                    origin: real::<T>(origin) / length,
+                })
+            }
+            ConstraintDescription::Pulley {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                distance,
+            } => Box::new(PulleyConstraint {
+                body_a: entity(body_a)?,
+                body_b: entity(body_b)?,
+                anchor_a: vector(anchor_a),
+                anchor_b: vector(anchor_b),
+                distance: real(distance),
+            }),
+            ConstraintDescription::Hinge {
+                body_a,
+                local_a,
+                body_b,
+                local_b,
+                axis,
+            } => Box::new(HingeConstraint {
+                body_a: entity(body_a)?,
+                local_a: vector(local_a),
+                body_b: entity(body_b)?,
+                local_b: vector(local_b),
+                axis: vector(axis),
+            }),
+            ConstraintDescription::Pin {
+                body,
+                local,
+                anchor,
+                axis,
+            } => Box::new(PinConstraint {
+                body: entity(body)?,
+                local: vector(local),
+                anchor: vector(anchor),
+                axis: vector(axis),
+            }),
+            ConstraintDescription::Gear {
+                body_a,
+                body_b,
+                ratio,
+                phase,
+            } => Box::new(GearConstraint {
+                body_a: entity(body_a)?,
+                body_b: entity(body_b)?,
+                ratio: real(ratio),
+                phase: real(phase),
+            }),
+        })
+    }
+}
+
+fn is_origin(vector: &Vector2<f64>) -> bool {
+    *vector == Vector2::zeros()
+}
@@ -0,0 +1,112 @@
+//! Hash of the state of the world, to compare two runs of the same scene tick by tick.
+//! A run on desktop and a run on Android should give the same stream, the first tick where they differ is where to
+//! look for the operation that doesn't round the same way on both architectures.
+//! The stream is written as text, one ``tick hash`` line per tick, so ``diff`` works on it too.
+
+use crate::components::{AngularVelocity, Position, Rotation, Velocity};
+use crate::scalar::{Real, to_f64};
+use hecs::{Entity, World};
+use std::io::{self, BufRead, Write};
+
+/// hash of the world after the tick ``tick``, see ``GameContent::set_deterministic``
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickHash {
+    pub tick: u32,
+    pub hash: u64,
+}
+
+/// 64 bits FNV-1a, it doesn't depend on the platform nor on the version of the standard library like ``DefaultHasher``
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_real<T: Real>(&mut self, value: T) {
+        // the conversion to f64 is exact, every bit of an f32 is kept
+        self.write(to_f64(value).to_bits());
+    }
+}
+
+/// Hash the bits of the position, velocity, orientation and angular velocity of every body, in ``SubjectToPhysic``
+/// order. The position of the previous tick is included, the position based solvers derive the velocity from it
+pub fn state_hash<T: Real>(world: &World, physic_index_to_entity: &[Entity]) -> u64 {
+    let mut query = world.query::<(&Position<T>, &Velocity<T>, &Rotation<T>, &AngularVelocity<T>)>();
+    let view = query.view();
+    let mut hasher = Fnv::new();
+    for &e in physic_index_to_entity {
+        let (position, velocity, rotation, angular_velocity) = view.get(e).unwrap();
+        let values = [
+            position.actual.x,
+            position.actual.y,
+            position.last_tick.x,
+            position.last_tick.y,
+            velocity.x,
+            velocity.y,
+            rotation.actual,
+            rotation.last_tick,
+            angular_velocity.0,
+        ];
+        for value in values {
+            hasher.write_real(value);
+        }
+    }
+    hasher.0
+}
+
+/// ``header`` describes the run, the scene and the solver for example, it is written as a ``#`` comment
+pub fn write_hash_stream(header: &str, hashes: &[TickHash], mut out: impl Write) -> io::Result<()> {
+    for line in header.lines() {
+        writeln!(out, "# {line}")?;
+    }
+    for TickHash { tick, hash } in hashes {
+        writeln!(out, "{tick} {hash:016x}")?;
+    }
+    Ok(())
+}
+
+/// read what ``write_hash_stream`` wrote, the comments and the empty lines are skipped
+pub fn read_hash_stream(input: impl BufRead) -> io::Result<Vec<TickHash>> {
+    let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("not a tick hash: {line:?}"));
+    let mut hashes = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (tick, hash) = line.split_once(' ').ok_or_else(|| invalid(line))?;
+        hashes.push(TickHash {
+            tick: tick.parse().map_err(|_| invalid(line))?,
+            hash: u64::from_str_radix(hash.trim(), 16).map_err(|_| invalid(line))?,
+        });
+    }
+    Ok(hashes)
+}
+
+/// First tick both streams hashed where the states differ.
+/// A stream that stops earlier isn't a divergence, the shorter run may just have been stopped sooner
+pub fn first_divergence(a: &[TickHash], b: &[TickHash]) -> Option<u32> {
+    let (mut a, mut b) = (a.iter().peekable(), b.iter().peekable());
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if x.tick < y.tick {
+            a.next();
+        } else if y.tick < x.tick {
+            b.next();
+        } else if x.hash != y.hash {
+            return Some(x.tick);
+        } else {
+            a.next();
+            b.next();
+        }
+    }
+    None
+}
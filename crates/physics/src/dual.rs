@@ -0,0 +1,198 @@
+//! Forward mode automatic differentiation.
+//! A ``Dual`` carries a value and its derivative along one direction. Nesting them gives higher derivatives:
+//! the ``eps`` part of the ``eps`` part of a ``Dual<Dual<T>>`` is the second derivative along both directions.
+//! ``ConstraintFunction`` uses them to derive the jacobian and its time derivatives from C(q).
+
+use crate::scalar::{Real, sin_cos};
+use nalgebra::{
+    ClosedAddAssign, ClosedDivAssign, ClosedMulAssign, ClosedSubAssign, ComplexField, Vector2,
+};
+use num_traits::{One, Zero};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// The scalar a ``ConstraintFunction`` is written with, either the scalar of the simulation or a dual number.
+/// It works with ``Vector2``, except for the methods that need a ``RealField`` like ``Vector2::norm``,
+/// the free functions of this module replace them
+pub trait Differentiable<T: Real>:
+    nalgebra::Scalar
+    + Copy
+    + Zero
+    + One
+    + ClosedAddAssign
+    + ClosedSubAssign
+    + ClosedMulAssign
+    + ClosedDivAssign
+    + Neg<Output = Self>
+    + Send
+    + Sync
+{
+    /// a value that doesn't depend on the coordinates
+    fn constant(value: T) -> Self;
+    /// the value, without any derivative
+    fn value(self) -> T;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+}
+
+impl<T: Real> Differentiable<T> for T {
+    fn constant(value: T) -> Self {
+        value
+    }
+
+    fn value(self) -> T {
+        self
+    }
+
+    fn sqrt(self) -> Self {
+        ComplexField::sqrt(self)
+    }
+
+    fn sin(self) -> Self {
+        sin_cos(self).0
+    }
+
+    fn cos(self) -> Self {
+        sin_cos(self).1
+    }
+}
+
+/// ``re + eps * ε`` with ``ε² = 0``
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual<S> {
+    pub re: S,
+    pub eps: S,
+}
+
+impl<S> Dual<S> {
+    pub fn new(re: S, eps: S) -> Self {
+        Self { re, eps }
+    }
+}
+
+impl<S: Add<Output = S>> Add for Dual<S> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.eps + rhs.eps)
+    }
+}
+
+impl<S: Sub<Output = S>> Sub for Dual<S> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.eps - rhs.eps)
+    }
+}
+
+impl<S: Copy + Add<Output = S> + Mul<Output = S>> Mul for Dual<S> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.re * rhs.re, self.re * rhs.eps + self.eps * rhs.re)
+    }
+}
+
+impl<S: Copy + Sub<Output = S> + Mul<Output = S> + Div<Output = S>> Div for Dual<S> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        Self::new(
+            self.re / rhs.re,
+            (self.eps * rhs.re - self.re * rhs.eps) / (rhs.re * rhs.re),
+        )
+    }
+}
+
+impl<S: Neg<Output = S>> Neg for Dual<S> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.eps)
+    }
+}
+
+impl<S: Add<Output = S>> AddAssign for Dual<S>
+where
+    Self: Copy,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<S: Sub<Output = S>> SubAssign for Dual<S>
+where
+    Self: Copy,
+{
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<S: Copy + Add<Output = S> + Mul<Output = S>> MulAssign for Dual<S> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<S: Copy + Sub<Output = S> + Mul<Output = S> + Div<Output = S>> DivAssign for Dual<S> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl<S: Zero> Zero for Dual<S> {
+    fn zero() -> Self {
+        Self::new(S::zero(), S::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.eps.is_zero()
+    }
+}
+
+impl<S: Copy + Zero + One + Add<Output = S> + Mul<Output = S>> One for Dual<S> {
+    fn one() -> Self {
+        Self::new(S::one(), S::zero())
+    }
+}
+
+impl<T: Real, S: Differentiable<T>> Differentiable<T> for Dual<S> {
+    fn constant(value: T) -> Self {
+        Self::new(S::constant(value), S::zero())
+    }
+
+    fn value(self) -> T {
+        self.re.value()
+    }
+
+    /// the derivative of the square root at 0 is infinite, it is taken as 0 instead:
+    /// a point sitting on its anchor has no direction to be pulled along
+    fn sqrt(self) -> Self {
+        let re = self.re.sqrt();
+        if re.value() == T::zero() {
+            return Self::new(re, S::zero());
+        }
+        Self::new(re, self.eps / (re + re))
+    }
+
+    fn sin(self) -> Self {
+        Self::new(self.re.sin(), self.eps * self.re.cos())
+    }
+
+    fn cos(self) -> Self {
+        Self::new(self.re.cos(), -self.eps * self.re.sin())
+    }
+}
+
+/// a constant vector, see ``Differentiable::constant``
+pub fn lift<T: Real, S: Differentiable<T>>(vector: Vector2<T>) -> Vector2<S> {
+    vector.map(S::constant)
+}
+
+pub fn norm<T: Real, S: Differentiable<T>>(vector: Vector2<S>) -> S {
+    vector.dot(&vector).sqrt()
+}
+
+/// rotate ``vector`` by ``angle``
+pub fn rotate<T: Real, S: Differentiable<T>>(vector: Vector2<S>, angle: S) -> Vector2<S> {
+    let (sin, cos) = (angle.sin(), angle.cos());
+    Vector2::new(cos * vector.x - sin * vector.y, sin * vector.x + cos * vector.y)
+}
@@ -0,0 +1,304 @@
+//! Split the world into islands: groups of bodies linked by constraints, directly or through other bodies.
+//! Two islands don't share anything, they can be solved one after the other or at the same time.
+//! An island is solved in a world of its own holding a copy of its bodies, under the same entities,
+//! so the constraints don't need to be rebuilt and the solvers see a world as small as the island.
+
+use crate::Constraint;
+use crate::components::{
+    Acceleration, AngularVelocity, BodyKind, Inertia, Mass, Position, Rotation, SubjectToPhysic, Velocity,
+};
+use crate::scalar::Real;
+use crate::solvers::{IterationStats, SolveError, Solver, SolverContext};
+use hecs::{Entity, World};
+use nalgebra::{DVector, Vector2};
+
+/// Bodies and constraints of one island, both are indices in increasing order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Island {
+    /// ``SubjectToPhysic`` index of the bodies
+    pub(crate) bodies: Vec<usize>,
+    /// index of the constraints, contacts included
+    pub(crate) constraints: Vec<usize>,
+}
+
+/// Connected components of the constraint graph, sorted by their first body.
+/// The bodies without any constraint share a single island, putting each of them apart would cost more than
+/// integrating them together.
+/// Static and kinematic bodies don't link islands, nothing goes through them: they are copied in every island
+/// attached to them
+pub(crate) fn find_islands<T: Real>(world: &World, body_count: usize, constraints: &[&Constraint<T>]) -> Vec<Island> {
+    let mut parents: Vec<usize> = (0..body_count).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut dynamic = vec![true; body_count];
+    for (_, (index, kind)) in world.query::<(&SubjectToPhysic, &BodyKind<T>)>().iter() {
+        dynamic[index.0] = kind.is_dynamic();
+    }
+    let index = |e: Entity| world.get::<&SubjectToPhysic>(e).map(|index| index.0).ok();
+    let all_bodies: Vec<Vec<usize>> = constraints
+        .iter()
+        .map(|c| c.expression.bodies().into_iter().filter_map(index).collect())
+        .collect();
+    // the bodies that link the island together
+    let constraint_bodies: Vec<Vec<usize>> = all_bodies
+        .iter()
+        .map(|bodies| bodies.iter().copied().filter(|&body| dynamic[body]).collect())
+        .collect();
+    let mut constrained = vec![false; body_count];
+    for bodies in &constraint_bodies {
+        for &body in bodies {
+            constrained[body] = true;
+            let (a, b) = (root(&mut parents, bodies[0]), root(&mut parents, body));
+            parents[b.max(a)] = a.min(b);
+        }
+    }
+
+    let mut islands: Vec<Island> = Vec::new();
+    let mut island_of_root = vec![usize::MAX; body_count];
+    let mut free = Island::default();
+    for (body, &constrained) in constrained.iter().enumerate() {
+        if !constrained {
+            free.bodies.push(body);
+            continue;
+        }
+        let root = root(&mut parents, body);
+        if island_of_root[root] == usize::MAX {
+            island_of_root[root] = islands.len();
+            islands.push(Island::default());
+        }
+        islands[island_of_root[root]].bodies.push(body);
+    }
+    // static and kinematic bodies attached to nothing dynamic stay with the free bodies
+    let mut attached = vec![false; body_count];
+    for (i, bodies) in constraint_bodies.iter().enumerate() {
+        let island = match bodies.first() {
+            Some(&body) => &mut islands[island_of_root[root(&mut parents, body)]],
+            // a constraint without any dynamic body doesn't do anything, it is kept so that its force is reported
+            None if !islands.is_empty() => &mut islands[0],
+            None => &mut free,
+        };
+        island.constraints.push(i);
+        for &body in all_bodies[i].iter().filter(|&&body| !dynamic[body]) {
+            if !island.bodies.contains(&body) {
+                island.bodies.push(body);
+            }
+            attached[body] = true;
+        }
+    }
+    for island in islands.iter_mut() {
+        island.bodies.sort_unstable();
+    }
+    free.bodies.retain(|&body| dynamic[body] || !attached[body]);
+    free.bodies.sort_unstable();
+    if !free.bodies.is_empty() || !free.constraints.is_empty() {
+        let first = free.bodies.first().copied().unwrap_or(0);
+        let position = islands.partition_point(|island| island.bodies.first().is_some_and(|&body| body < first));
+        islands.insert(position, free);
+    }
+    islands
+}
+
+/// Everything an island needs besides its bodies and constraints
+#[derive(Clone, Copy)]
+pub(crate) struct Step<'a, T: Real> {
+    pub(crate) solver: &'a dyn Solver<T>,
+    pub(crate) gravity: Vector2<T>,
+    pub(crate) time_step: T,
+    pub(crate) substeps: usize,
+}
+
+/// What solving every island gave, merged back in the indices of the whole world
+pub(crate) struct Solution {
+    pub(crate) redundant_constraints: Vec<usize>,
+    pub(crate) iteration_stats: Option<IterationStats>,
+    /// the error of the first island that failed, the other islands are solved anyway
+    pub(crate) result: Result<(), SolveError>,
+}
+
+/// Solve every island and write the bodies and the applied corrections back.
+/// With ``parallel`` the islands are spread over the available threads
+pub(crate) fn solve_islands<T: Real>(
+    world: &mut World,
+    physic_index_to_entity: &[Entity],
+    islands: &[Island],
+    constraints: &[&Constraint<T>],
+    applied_correction: &mut DVector<T>,
+    step: Step<T>,
+    parallel: bool,
+) -> Solution {
+    let (shared_world, shared_correction): (&World, &DVector<T>) = (world, applied_correction);
+    let solve_chunk = |chunk: &[Island]| {
+        chunk
+            .iter()
+            .map(|island| {
+                solve_island(shared_world, physic_index_to_entity, island, constraints, shared_correction, step)
+            })
+            .collect::<Vec<_>>()
+    };
+    let threads = if parallel {
+        std::thread::available_parallelism().map_or(1, |threads| threads.get())
+    } else {
+        1
+    };
+    let chunks: Vec<Vec<IslandSolution<T>>> = if threads > 1 && islands.len() > 1 {
+        let chunk_size = islands.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = islands
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || solve_chunk(chunk)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        })
+    } else {
+        vec![solve_chunk(islands)]
+    };
+
+    let mut solution = Solution {
+        redundant_constraints: Vec::new(),
+        iteration_stats: None,
+        result: Ok(()),
+    };
+    for (island, island_solution) in islands.iter().zip(chunks.into_iter().flatten()) {
+        island_solution.write_back(world, island, applied_correction);
+        solution.redundant_constraints.extend(island_solution.redundant_constraints);
+        if let Some(stats) = island_solution.iteration_stats {
+            solution.iteration_stats.get_or_insert_with(IterationStats::default).merge(stats);
+        }
+        if solution.result.is_ok() {
+            solution.result = island_solution.result;
+        }
+    }
+    solution.redundant_constraints.sort_unstable();
+    solution
+}
+
+/// What solving one island gave
+struct IslandSolution<T: Real> {
+    /// the bodies of the island once solved
+    world: World,
+    /// entity of each body, in the order of ``Island::bodies``
+    entities: Vec<Entity>,
+    applied_correction: DVector<T>,
+    /// indices in the whole list of constraints
+    redundant_constraints: Vec<usize>,
+    iteration_stats: Option<IterationStats>,
+    result: Result<(), SolveError>,
+}
+
+impl<T: Real> IslandSolution<T> {
+    fn write_back(&self, world: &mut World, island: &Island, applied_correction: &mut DVector<T>) {
+        for &e in &self.entities {
+            let (position, velocity, acceleration, _, rotation, angular_velocity, _, kind) =
+                self.world.query_one::<BodyQuery<T>>(e).unwrap().get().map(copied).unwrap();
+            // static and kinematic bodies may be in several islands, they are driven anyway
+            if !kind.is_dynamic() {
+                continue;
+            }
+            let (p, v, a, r, w) = world
+                .query_one_mut::<(
+                    &mut Position<T>,
+                    &mut Velocity<T>,
+                    &mut Acceleration<T>,
+                    &mut Rotation<T>,
+                    &mut AngularVelocity<T>,
+                )>(e)
+                .unwrap();
+            (*p, *v, *a, *r, *w) = (position, velocity, acceleration, rotation, angular_velocity);
+        }
+        for (k, &i) in island.constraints.iter().enumerate() {
+            applied_correction[i] = self.applied_correction[k];
+        }
+    }
+}
+
+/// copy the bodies of ``island`` in a world of their own and solve it
+fn solve_island<T: Real>(
+    world: &World,
+    physic_index_to_entity: &[Entity],
+    island: &Island,
+    constraints: &[&Constraint<T>],
+    applied_correction: &DVector<T>,
+    step: Step<T>,
+) -> IslandSolution<T> {
+    let mut island_world = World::new();
+    let entities: Vec<Entity> = island.bodies.iter().map(|&body| physic_index_to_entity[body]).collect();
+    let mut query = world.query::<BodyQuery<T>>();
+    let view = query.view();
+    // each body is looked up by its entity and given its index within the island
+    for (local, &e) in entities.iter().enumerate() {
+        let (position, velocity, acceleration, mass, rotation, angular_velocity, inertia, kind) =
+            copied(view.get(e).unwrap());
+        island_world.spawn_at(
+            e,
+            (
+                position,
+                velocity,
+                acceleration,
+                SubjectToPhysic(local),
+                mass,
+                rotation,
+                angular_velocity,
+                inertia,
+                kind,
+            ),
+        );
+    }
+
+    let island_constraints: Vec<&Constraint<T>> = island.constraints.iter().map(|&i| constraints[i]).collect();
+    let mut island_correction =
+        DVector::from_iterator(island.constraints.len(), island.constraints.iter().map(|&i| applied_correction[i]));
+    let mut ctx = SolverContext::new(
+        &mut island_world,
+        island_constraints,
+        &entities,
+        &mut island_correction,
+        step.gravity,
+        step.time_step,
+        step.substeps,
+    );
+    let result = step.solver.solve(&mut ctx);
+    let redundant_constraints = ctx.redundant_constraints.iter().map(|&k| island.constraints[k]).collect();
+    let iteration_stats = ctx.iteration_stats;
+    IslandSolution {
+        world: island_world,
+        entities,
+        applied_correction: island_correction,
+        redundant_constraints,
+        iteration_stats,
+        result,
+    }
+}
+
+/// the components of a body that the solvers read or write
+type BodyQuery<'a, T> = (
+    &'a Position<T>,
+    &'a Velocity<T>,
+    &'a Acceleration<T>,
+    &'a Mass<T>,
+    &'a Rotation<T>,
+    &'a AngularVelocity<T>,
+    &'a Inertia<T>,
+    &'a BodyKind<T>,
+);
+
+type Body<T> = (
+    Position<T>,
+    Velocity<T>,
+    Acceleration<T>,
+    Mass<T>,
+    Rotation<T>,
+    AngularVelocity<T>,
+    Inertia<T>,
+    BodyKind<T>,
+);
+
+fn copied<T: Real>(body: BodyQuery<T>) -> Body<T> {
+    let (position, velocity, acceleration, mass, rotation, angular_velocity, inertia, kind) = body;
+    (*position, *velocity, *acceleration, *mass, *rotation, *angular_velocity, *inertia, kind.clone())
+}
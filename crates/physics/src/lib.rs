@@ -0,0 +1,1003 @@
+//! Headless physics core: the world, its bodies, constraints and solvers.
+//! Nothing in here depends on a GUI, simulations can be run from tests, benches or CLI tools.
+
+pub mod collision;
+pub mod components;
+pub mod constraints;
+pub mod determinism;
+pub mod dual;
+mod islands;
+pub mod persistence;
+pub mod scalar;
+pub mod scene;
+pub mod solvers;
+pub mod verification;
+
+use crate::collision::{Contact, Wall, detect_contacts};
+use crate::determinism::{TickHash, state_hash};
+use crate::islands::{Step, find_islands, solve_islands};
+use crate::components::{
+    Acceleration, AngularVelocity, BodyKind, Collider, Inertia, Mass, Position, Rotation, SubjectToPhysic, Trajectory,
+    Velocity,
+};
+use crate::constraints::{
+    ConstraintExpression, ConstraintWidget, DistanceConstraint, HingeConstraint, PinConstraint, PlaneConstraint,
+};
+use crate::scalar::{Real, real, to_f32, vector, vector_to_f32};
+use crate::solvers::{HybridV3, IterationStats, SolveError, Solver, SolverContext};
+use hecs::{Entity, NoSuchEntity, World};
+use nalgebra::{DVector, Vector2};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub use hecs;
+pub use nalgebra;
+
+/// Sign allowed for the multiplier of a constraint
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Bound {
+    /// C = 0, λ is free
+    Equality,
+    /// the constraint can only push along its gradient: C ≥ 0 and λ ≥ 0, a floor for example
+    Push,
+    /// the constraint can only pull: C ≤ 0 and λ ≤ 0, a rope for example
+    Pull,
+}
+
+impl Bound {
+    pub fn clamp<T: Real>(self, lambda: T) -> T {
+        match self {
+            Bound::Equality => lambda,
+            Bound::Push => lambda.max(T::zero()),
+            Bound::Pull => lambda.min(T::zero()),
+        }
+    }
+
+    /// the constraint is satisfied without any help, a slack rope or a body above the floor
+    pub fn is_inactive<T: Real>(self, c: T) -> bool {
+        match self {
+            Bound::Equality => false,
+            Bound::Push => c > T::zero(),
+            Bound::Pull => c < T::zero(),
+        }
+    }
+
+    /// how much the constraint is violated, unilateral constraints are only violated on one side
+    pub fn violation<T: Real>(self, c: T) -> T {
+        if self.is_inactive(c) { T::zero() } else { c }
+    }
+}
+
+/// Split the ticks where a single step isn't accurate enough, see ``GameContent::set_adaptive_stepping``.
+/// Every step is compared with two steps of half its length, when they disagree the step is done again
+/// with half the length, and so is a step that fails. The state of the half steps is the one kept
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveStepping {
+    /// largest distance allowed between a body after one step and after two half steps
+    pub position_tolerance: f64,
+    /// largest difference allowed between the mechanical energy after one step and after two half steps
+    pub energy_tolerance: f64,
+    /// largest violation of a rigid constraint allowed at the end of a step,
+    /// a violation that was already there at the beginning of the step doesn't split it
+    pub violation_tolerance: f64,
+    /// the steps are never shorter than the tick divided by 2^max_depth
+    pub max_depth: u32,
+}
+
+impl Default for AdaptiveStepping {
+    fn default() -> Self {
+        Self {
+            position_tolerance: 1e-4,
+            energy_tolerance: 1e-3,
+            violation_tolerance: 1e-3,
+            max_depth: 4,
+        }
+    }
+}
+
+struct Constraint<T: Real> {
+    expression: Box<dyn ConstraintExpression<T>>,
+    stiffness: T,
+    damping: T,
+    bound: Bound,
+    /// scale of every update made by the iterative solvers, 1 is a plain Gauss-Seidel step
+    relaxation: T,
+}
+
+/// The simulated world, ``T`` is the scalar used by the whole simulation, f32 or f64.
+/// ``WorldSnapshot`` is always in f32, it is only used for display
+pub struct GameContent<T: Real = f32> {
+    pub world: World,
+    physic_index_to_entity: Vec<Entity>,
+    constraints: Vec<Constraint<T>>,
+    /// temporary constraints generated by the collision detection, they are rebuilt every tick
+    contacts: Vec<Constraint<T>>,
+    walls: Vec<Wall<T>>,
+    applied_correction: DVector<T>,
+    gravity: Vector2<T>,
+    time_step: T,
+    /// number of substeps per tick for the sub-stepping solvers, the others ignore it
+    substeps: usize,
+    age: u32,
+    /// simulated time since the scene was built, it drives the kinematic bodies
+    time: T,
+    calculation_time: Duration,
+    /// see ``WorldSnapshot::redundant_constraints``
+    redundant_constraints: Vec<usize>,
+    /// see ``WorldSnapshot::iteration_stats``
+    iteration_stats: Option<IterationStats>,
+    /// solve the islands on every available thread, see ``set_parallel_islands``
+    parallel_islands: bool,
+    /// see ``WorldSnapshot::islands``
+    island_count: usize,
+    /// ``None`` solves every tick in a single step, see ``set_adaptive_stepping``
+    adaptive_stepping: Option<AdaptiveStepping>,
+    /// length of the last step, the position based solvers keep the position of the step before
+    last_step: T,
+    /// see ``WorldSnapshot::tick_substeps``
+    tick_substeps: usize,
+    /// see ``set_deterministic``
+    deterministic: bool,
+    hash_stream: Vec<TickHash>,
+    pub solver: Arc<dyn Solver<T>>,
+}
+
+impl<T: Real> GameContent<T> {
+    pub fn empty(time_step: T) -> Self {
+        Self {
+            world: World::new(),
+            constraints: Vec::new(),
+            contacts: Vec::new(),
+            walls: Vec::new(),
+            physic_index_to_entity: Vec::new(),
+            gravity: vector(0.0, -9.81),
+            applied_correction: DVector::zeros(0),
+            time_step,
+            substeps: 4,
+            age: 0,
+            time: T::zero(),
+            calculation_time: Duration::from_millis(0),
+            redundant_constraints: Vec::new(),
+            iteration_stats: None,
+            parallel_islands: false,
+            island_count: 0,
+            adaptive_stepping: None,
+            last_step: time_step,
+            tick_substeps: 0,
+            deterministic: false,
+            hash_stream: Vec::new(),
+            solver: Arc::new(HybridV3),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.world.clear();
+        self.physic_index_to_entity.clear();
+        self.constraints.clear();
+        self.contacts.clear();
+        self.walls.clear();
+        self.applied_correction = DVector::zeros(0);
+        self.redundant_constraints.clear();
+        self.iteration_stats = None;
+        self.age = 0;
+        self.time = T::zero();
+        self.last_step = self.time_step;
+        self.tick_substeps = 0;
+        self.hash_stream.clear();
+    }
+
+    /// point masses still have an orientation, but constraints can't make them spin
+    pub fn add_body(&mut self, pos: Vector2<T>, velocity: Vector2<T>, mass: T) -> Entity {
+        let inertia = Inertia {
+            inertia: T::zero(),
+            inv_inertia: T::zero(),
+        };
+        self.spawn_body(pos, T::zero(), velocity, T::zero(), Mass::new(mass), inertia, BodyKind::Dynamic)
+    }
+
+    pub fn add_rigid_body(
+        &mut self,
+        pos: Vector2<T>,
+        angle: T,
+        velocity: Vector2<T>,
+        angular_velocity: T,
+        mass: T,
+        inertia: T,
+    ) -> Entity {
+        let inertia = Inertia {
+            inertia,
+            inv_inertia: inertia.recip(),
+        };
+        self.spawn_body(pos, angle, velocity, angular_velocity, Mass::new(mass), inertia, BodyKind::Dynamic)
+    }
+
+    /// a body that never moves, constraints can attach to it like to any other body
+    pub fn add_static_body(&mut self, pos: Vector2<T>, angle: T) -> Entity {
+        let (mass, inertia) = Self::infinite_mass();
+        self.spawn_body(pos, angle, Vector2::zeros(), T::zero(), mass, inertia, BodyKind::Static)
+    }
+
+    /// a body following ``trajectory``, neither gravity nor constraints can deviate it
+    pub fn add_kinematic_body(&mut self, trajectory: impl Trajectory<T> + 'static) -> Entity {
+        self.spawn_kinematic_body(BodyKind::kinematic(trajectory))
+    }
+
+    /// ``kind`` must be ``BodyKind::Kinematic``, the body starts where its trajectory is
+    fn spawn_kinematic_body(&mut self, kind: BodyKind<T>) -> Entity {
+        let (mass, inertia) = Self::infinite_mass();
+        let state = kind.state(self.time).unwrap();
+        self.spawn_body(
+            state.coordinates.position,
+            state.coordinates.angle,
+            state.velocity.position,
+            state.velocity.angle,
+            mass,
+            inertia,
+            kind,
+        )
+    }
+
+    fn infinite_mass() -> (Mass<T>, Inertia<T>) {
+        let mass = Mass {
+            mass: real(f64::INFINITY),
+            inv_mass: T::zero(),
+        };
+        let inertia = Inertia {
+            inertia: real(f64::INFINITY),
+            inv_inertia: T::zero(),
+        };
+        (mass, inertia)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_body(
+        &mut self,
+        pos: Vector2<T>,
+        angle: T,
+        velocity: Vector2<T>,
+        angular_velocity: T,
+        mass: Mass<T>,
+        inertia: Inertia<T>,
+        kind: BodyKind<T>,
+    ) -> Entity {
+        let index = SubjectToPhysic(self.physic_index_to_entity.len());
+        let entity = self.world.spawn((
+            Position {
+                actual: pos,
+                last_tick: pos - velocity * self.time_step,
+            },
+            Velocity(velocity),
+            Acceleration::<T>(Vector2::zeros()),
+            index,
+            mass,
+            Rotation {
+                actual: angle,
+                last_tick: angle - angular_velocity * self.time_step,
+            },
+            AngularVelocity(angular_velocity),
+            inertia,
+            kind,
+        ));
+        self.physic_index_to_entity.push(entity);
+        entity
+    }
+
+    /// Despawn ``body`` and every constraint and contact attached to it, the other bodies keep their order.
+    /// See ``remove_constraint`` for the indices of the remaining constraints
+    pub fn remove_body(&mut self, body: Entity) -> Result<(), NoSuchEntity> {
+        let index = self.world.get::<&SubjectToPhysic>(body).map(|index| index.0).ok();
+        self.world.despawn(body)?;
+        if let Some(index) = index.filter(|&index| self.physic_index_to_entity.get(index) == Some(&body)) {
+            self.physic_index_to_entity.remove(index);
+            for (i, &e) in self.physic_index_to_entity.iter().enumerate().skip(index) {
+                *self.world.get::<&mut SubjectToPhysic>(e).unwrap() = SubjectToPhysic(i);
+            }
+        }
+        for i in (0..self.constraints.len()).rev() {
+            if self.constraints[i].expression.bodies().contains(&body) {
+                self.remove_constraint(i);
+            }
+        }
+        // the contacts stay until the next tick detects them again, those of the body must go now
+        for i in (0..self.contacts.len()).rev() {
+            if self.contacts[i].expression.bodies().contains(&body) {
+                self.contacts.remove(i);
+                self.remove_row(self.constraints.len() + i);
+            }
+        }
+        Ok(())
+    }
+
+    /// give a circle shape to the body, it will collide with other colliders and walls
+    pub fn add_collider(&mut self, body: Entity, radius: T) {
+        self.world.insert_one(body, Collider { radius }).unwrap();
+    }
+
+    /// a static half plane, only colliders are stopped by it
+    pub fn add_wall(&mut self, normal: Vector2<T>, origin: Vector2<T>) {
+        self.walls.push(Wall::new(normal, origin));
+    }
+
+    /// returns the index of the constraint, see ``set_relaxation``
+    pub fn add_stiff_constraint(&mut self, constraint: impl ConstraintExpression<T> + 'static) -> usize {
+        self.add_constraint(constraint, real(f64::INFINITY), T::zero())
+    }
+
+    /// soft constraints are only soft if the solver supports it, otherwise they are rigid
+    pub fn add_constraint(
+        &mut self,
+        constraint: impl ConstraintExpression<T> + 'static,
+        stiffness: T,
+        damping: T,
+    ) -> usize {
+        self.push_constraint(Constraint {
+            expression: Box::new(constraint),
+            stiffness,
+            damping,
+            bound: Bound::Equality,
+            relaxation: T::one(),
+        })
+    }
+
+    /// rigid constraint that can only push or only pull, see ``Bound``
+    pub fn add_unilateral_constraint(
+        &mut self,
+        constraint: impl ConstraintExpression<T> + 'static,
+        bound: Bound,
+    ) -> usize {
+        self.push_constraint(Constraint {
+            expression: Box::new(constraint),
+            stiffness: real(f64::INFINITY),
+            damping: T::zero(),
+            bound,
+            relaxation: T::one(),
+        })
+    }
+
+    fn push_constraint(&mut self, constraint: Constraint<T>) -> usize {
+        let index = self.constraints.len();
+        self.constraints.push(constraint);
+        // the contacts come after the constraints, the other constraints keep their warm start
+        if index <= self.applied_correction.len() {
+            let correction = std::mem::replace(&mut self.applied_correction, DVector::zeros(0));
+            self.applied_correction = correction.insert_row(index, T::zero());
+        }
+        index
+    }
+
+    /// Remove the constraint at ``index``, the constraints after it move down by one like in a ``Vec``
+    pub fn remove_constraint(&mut self, index: usize) {
+        self.constraints.remove(index);
+        self.remove_row(index);
+    }
+
+    /// forget the warm start of the constraint or contact at ``index``, the rows after it move down by one
+    fn remove_row(&mut self, index: usize) {
+        if index < self.applied_correction.len() {
+            let correction = std::mem::replace(&mut self.applied_correction, DVector::zeros(0));
+            self.applied_correction = correction.remove_row(index);
+        }
+        self.redundant_constraints.retain(|&i| i != index);
+        for i in &mut self.redundant_constraints {
+            if *i > index {
+                *i -= 1;
+            }
+        }
+    }
+
+    /// Scale the updates the iterative solvers make to the constraint at ``index``, it defaults to 1.
+    /// Below 1 calms down redundant constraints fighting each other, above 1 speeds up long chains.
+    /// Solvers using a factorisation ignore it
+    pub fn set_relaxation(&mut self, index: usize, relaxation: T) {
+        self.constraints[index].relaxation = relaxation;
+    }
+
+    /// hold two attachment points together, the bodies are free to rotate around it
+    pub fn add_hinge(
+        &mut self,
+        body_a: Entity,
+        local_a: Vector2<T>,
+        body_b: Entity,
+        local_b: Vector2<T>,
+    ) {
+        for axis in [Vector2::x(), Vector2::y()] {
+            self.add_stiff_constraint(HingeConstraint {
+                body_a,
+                local_a,
+                body_b,
+                local_b,
+                axis,
+            });
+        }
+    }
+
+    /// hold an attachment point on a fixed anchor, the body is free to rotate around it
+    pub fn add_pin(&mut self, body: Entity, local: Vector2<T>, anchor: Vector2<T>) {
+        for axis in [Vector2::x(), Vector2::y()] {
+            self.add_stiff_constraint(PinConstraint {
+                body,
+                local,
+                anchor,
+                axis,
+            });
+        }
+    }
+
+    pub fn gravity(&self) -> Vector2<T> {
+        self.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vector2<T>) {
+        self.gravity = gravity;
+    }
+
+    pub fn time_step(&self) -> T {
+        self.time_step
+    }
+
+    pub fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    /// only the sub-stepping solvers use it, at least one substep is always done
+    pub fn set_substeps(&mut self, substeps: usize) {
+        self.substeps = substeps.max(1);
+    }
+
+    pub fn parallel_islands(&self) -> bool {
+        self.parallel_islands
+    }
+
+    /// Solve the independent islands of the scene at the same time, see ``islands``.
+    /// A scene made of a single island is always solved on the calling thread
+    pub fn set_parallel_islands(&mut self, parallel: bool) {
+        self.parallel_islands = parallel;
+    }
+
+    /// number of ticks since the scene was built
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    pub fn adaptive_stepping(&self) -> Option<AdaptiveStepping> {
+        self.adaptive_stepping
+    }
+
+    /// Split the ticks into shorter steps when the error of a step is over the tolerances of ``settings``.
+    /// ``None``, the default, solves every tick in a single step
+    pub fn set_adaptive_stepping(&mut self, settings: Option<AdaptiveStepping>) {
+        self.adaptive_stepping = settings;
+    }
+
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Solve every island on the calling thread and hash the state of the bodies after each tick, see ``determinism``.
+    /// Switching it on starts a new stream
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        if deterministic && !self.deterministic {
+            self.hash_stream.clear();
+        }
+        self.deterministic = deterministic;
+    }
+
+    /// hash of every tick solved since the scene was built or ``set_deterministic`` was switched on
+    pub fn hash_stream(&self) -> &[TickHash] {
+        &self.hash_stream
+    }
+
+    /// simulated time since the scene was built
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    /// true when bodies were spawned or despawned straight in ``world``,
+    /// the ones added or removed through ``GameContent`` are indexed right away
+    fn solver_index_is_stale(&self) -> bool {
+        let mut query = self.world.query::<&SubjectToPhysic>();
+        query.iter().len() != self.physic_index_to_entity.len()
+            || query
+                .iter()
+                .any(|(e, index)| self.physic_index_to_entity.get(index.0) != Some(&e))
+    }
+
+    /// index all the bodies again, the ones already indexed keep their order and the new ones come last
+    fn update_solver_index(&mut self) {
+        let mut bodies: Vec<(usize, Entity)> = self
+            .world
+            .query::<&SubjectToPhysic>()
+            .iter()
+            .map(|(e, index)| {
+                let indexed = self.physic_index_to_entity.get(index.0) == Some(&e);
+                (if indexed { index.0 } else { usize::MAX }, e)
+            })
+            .collect();
+        bodies.sort_by_key(|&(index, _)| index);
+        self.physic_index_to_entity = bodies.into_iter().map(|(_, e)| e).collect();
+        for (i, &e) in self.physic_index_to_entity.iter().enumerate() {
+            *self.world.get::<&mut SubjectToPhysic>(e).unwrap() = SubjectToPhysic(i);
+        }
+    }
+
+    /// energies and violations of the current state
+    fn measure(&self) -> Measures<T> {
+        let mut query = self
+            .world
+            .query::<(&Position<T>, &Velocity<T>, &Mass<T>, &AngularVelocity<T>, &Inertia<T>, &BodyKind<T>)>();
+        let half = real::<T>(0.5);
+        // static and kinematic bodies have an infinite mass, their energy doesn't mean anything
+        let (kinetic_energy, potential_energy) = query
+            .into_iter()
+            .filter(|(_, (.., kind))| kind.is_dynamic())
+            .map(|(_, (pos, velocity, mass, angular_velocity, inertia, _))| {
+                let kinetic_energy = half * mass.inv_mass.recip() * velocity.norm_squared()
+                    + half * inertia.inertia * angular_velocity.0 * angular_velocity.0;
+                let potential_energy = -mass.mass * self.gravity.dot(pos);
+                (kinetic_energy, potential_energy)
+            })
+            .fold((T::zero(), T::zero()), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        let mut query = self.world.query::<(&Position<T>, &Rotation<T>)>();
+        let view = query.view();
+        let mut rigid_violation = T::zero();
+        let (elastic_energy, violation_sum) = self
+            .constraints
+            .iter()
+            .chain(self.contacts.iter())
+            .map(|c| {
+                let violation = c.bound.violation(c.expression.evaluate_c(&view));
+                let absolute_violation = violation.abs();
+                if !c.stiffness.is_finite() {
+                    rigid_violation = rigid_violation.max(absolute_violation);
+                    (T::zero(), absolute_violation)
+                } else {
+                    (half * c.stiffness * violation * violation, absolute_violation)
+                }
+            })
+            .fold((T::zero(), T::zero()), |(a, b), (c, d)| (a + c, b + d));
+
+        let constraint_count = self.constraints.len() + self.contacts.len();
+        let violation_mean = if constraint_count == 0 {
+            T::zero()
+        } else {
+            violation_sum / real(constraint_count as f64)
+        };
+        Measures {
+            kinetic_energy,
+            potential_energy,
+            elastic_energy,
+            violation_mean,
+            rigid_violation,
+        }
+    }
+
+    pub fn take_snapshot(&mut self) -> WorldSnapshot {
+        let measures = self.measure();
+
+        let mut query = self.world.query::<(&Position<T>, &Rotation<T>, &Inertia<T>)>();
+        let view = query.view();
+        let (pos, angles) = self
+            .physic_index_to_entity
+            .iter()
+            .cloned()
+            .map(|e| {
+                let (pos, rot, inertia) = view.get(e).unwrap();
+                let angle = (inertia.inv_inertia != T::zero()).then_some(to_f32(rot.actual));
+                (vector_to_f32(pos.actual), angle)
+            })
+            .unzip();
+
+        let mut query = self.world.query::<Option<&Collider<T>>>();
+        let view = query.view();
+        let radii = self
+            .physic_index_to_entity
+            .iter()
+            .map(|e| view.get(*e).unwrap().map(|collider| to_f32(collider.radius)))
+            .collect();
+
+        let mut query = self.world.query::<&SubjectToPhysic>();
+        let view = query.view();
+        let convertor = |e: Entity| view.get(e).unwrap().0;
+
+        let widget_iter = self.constraints.iter().map(|c| c.expression.widget(&convertor));
+        let force_iter = self.applied_correction.iter().cloned().map(to_f32);
+        // contacts are not drawn, the circles touching each other already show them
+        let mut links: Vec<_> = widget_iter.zip(force_iter).collect();
+        links.extend(
+            self.walls
+                .iter()
+                .map(|wall| {
+                    let widget = ConstraintWidget::Plane(vector_to_f32(wall.normal), -to_f32(wall.origin));
+                    (widget, 0.0)
+                }),
+        );
+
+        let r = WorldSnapshot {
+            pos,
+            angles,
+            radii,
+            links,
+            kinetic_energy: to_f32(measures.kinetic_energy),
+            potential_energy: to_f32(measures.potential_energy),
+            elastic_energy: to_f32(measures.elastic_energy),
+            date: self.age,
+            violation_mean: to_f32(measures.violation_mean),
+            calculation_time: self.calculation_time,
+            redundant_constraints: self.redundant_constraints.clone(),
+            islands: self.island_count,
+            iteration_stats: self.iteration_stats,
+            tick_substeps: self.tick_substeps,
+            // ``solve`` hashed this tick already, a tick that failed has no hash
+            state_hash: self
+                .hash_stream
+                .last()
+                .filter(|hash| self.deterministic && hash.tick == self.age)
+                .map(|hash| hash.hash),
+            error: None,
+            timestamp: None,
+        };
+        self.age += 1;
+        r
+    }
+
+    /// Advance the world by one tick.
+    /// On error the world is left where the solver stopped, it should be reset before being solved again
+    pub fn solve(&mut self) -> Result<(), SolveError> {
+        if self.solver_index_is_stale() {
+            self.update_solver_index()
+        }
+        for (constraint, c) in self.constraints.iter().enumerate() {
+            for body in c.expression.bodies() {
+                if self.world.satisfies::<&SubjectToPhysic>(body) != Ok(true) {
+                    return Err(SolveError::MissingBody { constraint, body });
+                }
+            }
+        }
+        let begin = Instant::now();
+        match self.adaptive_stepping {
+            Some(settings) => self.adaptive_step(settings)?,
+            None => {
+                self.tick_substeps = 1;
+                self.step(self.time_step)?
+            }
+        };
+        self.calculation_time = begin.elapsed();
+        if self.deterministic {
+            let hash = state_hash::<T>(&self.world, &self.physic_index_to_entity);
+            self.hash_stream.push(TickHash { tick: self.age, hash });
+        }
+        Ok(())
+    }
+
+    /// Advance the world by ``time_step``
+    fn step(&mut self, time_step: T) -> Result<(), SolveError> {
+        self.rescale_last_step(time_step);
+        self.drive_bodies(self.time, time_step);
+        self.update_contacts(time_step);
+        let constraints: Vec<&Constraint<T>> = self.constraints.iter().chain(self.contacts.iter()).collect();
+        if self.applied_correction.len() != self.constraints.len() + self.contacts.len() {
+            // the number of contacts changes every tick, the previous correction can't be used as a guess
+            self.applied_correction = DVector::zeros(self.constraints.len() + self.contacts.len());
+        }
+        let solver = self.solver.clone();
+        let islands = find_islands(&self.world, self.physic_index_to_entity.len(), &constraints);
+        self.island_count = islands.len();
+        if islands.len() > 1 {
+            let step = Step {
+                solver: solver.as_ref(),
+                gravity: self.gravity,
+                time_step,
+                substeps: self.substeps,
+            };
+            let solution = solve_islands(
+                &mut self.world,
+                &self.physic_index_to_entity,
+                &islands,
+                &constraints,
+                &mut self.applied_correction,
+                step,
+                // the islands don't share anything, but a single thread leaves nothing to the scheduler
+                self.parallel_islands && !self.deterministic,
+            );
+            self.redundant_constraints = solution.redundant_constraints;
+            self.iteration_stats = solution.iteration_stats;
+            solution.result?;
+        } else {
+            // a single island is solved in place, there is nothing to copy
+            let mut ctx = SolverContext::new(
+                &mut self.world,
+                constraints,
+                &self.physic_index_to_entity,
+                &mut self.applied_correction,
+                self.gravity,
+                time_step,
+                self.substeps,
+            );
+            let result = solver.solve(&mut ctx);
+            self.redundant_constraints = ctx.redundant_constraints;
+            self.iteration_stats = ctx.iteration_stats;
+            result?;
+        }
+        self.time += time_step;
+        self.last_step = time_step;
+        self.drive_bodies(self.time, time_step);
+        self.check_finite()
+    }
+
+    /// Advance the world by one tick split in steps short enough for ``settings``
+    fn adaptive_step(&mut self, settings: AdaptiveStepping) -> Result<(), SolveError> {
+        // the tick is counted in steps of the shortest length
+        let shortest_steps = 1usize << settings.max_depth;
+        let mut done = 0;
+        let mut depth = 0;
+        self.tick_substeps = 0;
+        while done < shortest_steps {
+            let time_step = self.time_step / real((1usize << depth) as f64);
+            if depth == settings.max_depth {
+                self.step(time_step)?;
+                done += 1;
+                self.tick_substeps += 1;
+                continue;
+            }
+
+            let start = self.checkpoint();
+            match self.compare_steps(time_step, &start, settings) {
+                // the half steps are as short as the steps can be, there is nothing better to do
+                Ok(accurate) if accurate || depth + 1 == settings.max_depth => {
+                    done += shortest_steps >> depth;
+                    self.tick_substeps += 2;
+                }
+                // a stiff scene can make a step too long fail outright, it is split like an inaccurate one
+                _ => {
+                    self.restore_checkpoint(&start);
+                    depth += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Solve ``time_step`` from ``start`` in a single step and in two halves, and tell whether they agree within
+    /// ``settings``. The world is left at the end of the two halves
+    fn compare_steps(
+        &mut self,
+        time_step: T,
+        start: &Checkpoint<T>,
+        settings: AdaptiveStepping,
+    ) -> Result<bool, SolveError> {
+        let start_measures = self.measure();
+        self.step(time_step)?;
+        let single = self.checkpoint();
+        let single_energy = self.measure().mechanical_energy();
+        self.restore_checkpoint(start);
+        let half_step = time_step * real(0.5);
+        self.step(half_step)?;
+        self.step(half_step)?;
+        let halves = self.checkpoint();
+        let measures = self.measure();
+
+        let position_error = single
+            .bodies
+            .iter()
+            .zip(&halves.bodies)
+            .map(|(a, b)| (a.0.actual - b.0.actual).norm())
+            .fold(T::zero(), T::max);
+        let energy_error = (single_energy - measures.mechanical_energy()).abs();
+        let violation = measures.rigid_violation;
+        Ok(position_error <= real(settings.position_tolerance)
+            && energy_error <= real(settings.energy_tolerance)
+            && (violation <= real(settings.violation_tolerance) || violation <= start_measures.rigid_violation))
+    }
+
+    /// The position based solvers find the velocity from the position of the step before,
+    /// when the length of the steps changes that position is moved to where the body was ``time_step`` earlier
+    fn rescale_last_step(&mut self, time_step: T) {
+        if time_step == self.last_step {
+            return;
+        }
+        let ratio = time_step / self.last_step;
+        for (_, (position, rotation, kind)) in self
+            .world
+            .query_mut::<(&mut Position<T>, &mut Rotation<T>, &BodyKind<T>)>()
+        {
+            // ``drive_bodies`` takes care of the others
+            if kind.is_dynamic() {
+                position.last_tick = position.actual - (position.actual - position.last_tick) * ratio;
+                rotation.last_tick = rotation.actual - (rotation.actual - rotation.last_tick) * ratio;
+            }
+        }
+        self.last_step = time_step;
+    }
+
+    /// everything a step changes, in the order of ``physic_index_to_entity``
+    fn checkpoint(&self) -> Checkpoint<T> {
+        let mut query = self
+            .world
+            .query::<(&Position<T>, &Rotation<T>, &Velocity<T>, &AngularVelocity<T>, &Acceleration<T>)>();
+        let view = query.view();
+        let bodies = self
+            .physic_index_to_entity
+            .iter()
+            .map(|&e| {
+                let (position, rotation, velocity, angular_velocity, acceleration) = view.get(e).unwrap();
+                (*position, *rotation, *velocity, *angular_velocity, *acceleration)
+            })
+            .collect();
+        Checkpoint {
+            bodies,
+            applied_correction: self.applied_correction.clone(),
+            time: self.time,
+            last_step: self.last_step,
+        }
+    }
+
+    fn restore_checkpoint(&mut self, state: &Checkpoint<T>) {
+        for (&e, body) in self.physic_index_to_entity.iter().zip(&state.bodies) {
+            let (position, rotation, velocity, angular_velocity, acceleration) = self
+                .world
+                .query_one_mut::<(
+                    &mut Position<T>,
+                    &mut Rotation<T>,
+                    &mut Velocity<T>,
+                    &mut AngularVelocity<T>,
+                    &mut Acceleration<T>,
+                )>(e)
+                .unwrap();
+            (*position, *rotation, *velocity, *angular_velocity, *acceleration) = *body;
+        }
+        self.applied_correction = state.applied_correction.clone();
+        self.time = state.time;
+        self.last_step = state.last_step;
+    }
+
+    /// Put the kinematic bodies where their trajectory says at ``time`` and the static ones back at rest.
+    /// The solvers integrate them like the other bodies, this overrides whatever they did
+    fn drive_bodies(&mut self, time: T, time_step: T) {
+        for (_, (position, rotation, velocity, angular_velocity, acceleration, kind)) in self
+            .world
+            .query_mut::<(
+                &mut Position<T>,
+                &mut Rotation<T>,
+                &mut Velocity<T>,
+                &mut AngularVelocity<T>,
+                &mut Acceleration<T>,
+                &BodyKind<T>,
+            )>()
+        {
+            match kind {
+                BodyKind::Dynamic => {}
+                BodyKind::Static => {
+                    position.last_tick = position.actual;
+                    rotation.last_tick = rotation.actual;
+                    velocity.0 = Vector2::zeros();
+                    angular_velocity.0 = T::zero();
+                    acceleration.0 = Vector2::zeros();
+                }
+                BodyKind::Kinematic(trajectory, _) => {
+                    let state = trajectory(time);
+                    // the position based solvers derive the velocity from the last tick
+                    let last = trajectory(time - time_step).coordinates;
+                    *position = Position {
+                        actual: state.coordinates.position,
+                        last_tick: last.position,
+                    };
+                    *rotation = Rotation {
+                        actual: state.coordinates.angle,
+                        last_tick: last.angle,
+                    };
+                    velocity.0 = state.velocity.position;
+                    angular_velocity.0 = state.velocity.angle;
+                    acceleration.0 = state.acceleration.position;
+                }
+            }
+        }
+    }
+
+    /// ``NotANumber`` if a position or a velocity diverged
+    fn check_finite(&self) -> Result<(), SolveError> {
+        let mut query = self
+            .world
+            .query::<(&Position<T>, &Rotation<T>, &Velocity<T>, &AngularVelocity<T>)>();
+        let finite = query.iter().all(|(_, (position, rotation, velocity, angular_velocity))| {
+            position.actual.iter().all(|x| x.is_finite())
+                && rotation.actual.is_finite()
+                && velocity.0.iter().all(|x| x.is_finite())
+                && angular_velocity.0.is_finite()
+        });
+        if finite { Ok(()) } else { Err(SolveError::NotANumber) }
+    }
+
+    /// replace the contacts of the last tick by the ones of the current state
+    fn update_contacts(&mut self, time_step: T) {
+        self.contacts = detect_contacts(&self.world, &self.walls, time_step)
+            .into_iter()
+            .map(|contact| {
+                let expression: Box<dyn ConstraintExpression<T>> = match contact {
+                    Contact::Bodies {
+                        body_a,
+                        body_b,
+                        distance,
+                    } => Box::new(DistanceConstraint {
+                        body_a,
+                        local_a: Vector2::zeros(),
+                        body_b,
+                        local_b: Vector2::zeros(),
+                        distance,
+                    }),
+                    Contact::Wall {
+                        body,
+                        normal,
+                        origin,
+                    } => Box::new(PlaneConstraint::new(body, normal, normal * origin)),
+                };
+                Constraint {
+                    expression,
+                    stiffness: real(f64::INFINITY),
+                    damping: T::zero(),
+                    bound: Bound::Push,
+                    relaxation: T::one(),
+                }
+            })
+            .collect();
+    }
+}
+
+/// see ``GameContent::measure``
+struct Measures<T: Real> {
+    kinetic_energy: T,
+    potential_energy: T,
+    elastic_energy: T,
+    violation_mean: T,
+    /// largest violation of a rigid constraint, contacts included
+    rigid_violation: T,
+}
+
+impl<T: Real> Measures<T> {
+    fn mechanical_energy(&self) -> T {
+        self.kinetic_energy + self.potential_energy + self.elastic_energy
+    }
+}
+
+/// the components of a body a step changes
+type BodyState<T> = (Position<T>, Rotation<T>, Velocity<T>, AngularVelocity<T>, Acceleration<T>);
+
+/// the bodies and the warm start, to do a step again, see ``GameContent::checkpoint``
+struct Checkpoint<T: Real> {
+    bodies: Vec<BodyState<T>>,
+    applied_correction: DVector<T>,
+    time: T,
+    last_step: T,
+}
+
+#[derive(Default)]
+pub struct WorldSnapshot {
+    pub pos: Vec<Vector2<f32>>,
+    /// orientation of each body, ``None`` for point masses
+    pub angles: Vec<Option<f32>>,
+    /// radius of each body, ``None`` for bodies without collider
+    pub radii: Vec<Option<f32>>,
+    pub links: Vec<(ConstraintWidget, f32)>,
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub elastic_energy: f32,
+    pub date: u32,
+    pub calculation_time: Duration,
+    pub violation_mean: f32,
+    /// constraints that are a combination of other ones at the beginning of the tick, they apply no force.
+    /// The constraints of the scene come first, in the order of ``links``, then the contacts
+    pub redundant_constraints: Vec<usize>,
+    /// number of independent groups of bodies the scene was split into, see ``islands``
+    pub islands: usize,
+    /// convergence of the iterative solver, ``None`` for the solvers that don't report it
+    pub iteration_stats: Option<IterationStats>,
+    /// number of steps the last tick was split into, always 1 without ``AdaptiveStepping``
+    pub tick_substeps: usize,
+    /// see ``determinism::state_hash``, only computed by ``GameContent::set_deterministic``
+    pub state_hash: Option<u64>,
+    /// why the simulation stopped, the snapshot is the state the failed tick left
+    pub error: Option<SolveError>,
+    /// when the tick that produced the snapshot was due, it is up to whoever runs the ticks to set it
+    pub timestamp: Option<Instant>,
+}
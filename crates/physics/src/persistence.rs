@@ -0,0 +1,327 @@
+//! Save the whole state of a ``GameContent`` and load it back, to reproduce a run from an interesting moment.
+//! Everything is stored in f64 whatever the scalar of the simulation: an f32 converted to f64 and back is unchanged,
+//! so a loaded simulation goes on exactly like the one that was saved. The text format is RON.
+//! Only the built-in constraints and trajectories can be saved, see ``ConstraintDescription`` and ``TrajectoryDescription``
+
+use crate::collision::Wall;
+use crate::components::{
+    Acceleration, AngularVelocity, BodyKind, BodyKindDescription, Collider, Inertia, Mass, Position, Rotation,
+    SubjectToPhysic, Velocity,
+};
+use crate::constraints::ConstraintDescription;
+use crate::scalar::{Real, real, to_f64};
+use crate::solvers::SolverRegistry;
+use crate::{AdaptiveStepping, Bound, Constraint, GameContent};
+use hecs::Entity;
+use nalgebra::{DVector, Vector2};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedState {
+    /// name of the solver in the ``SolverRegistry``
+    pub solver: String,
+    pub time_step: f64,
+    pub substeps: usize,
+    pub gravity: Vector2<f64>,
+    pub adaptive_stepping: Option<AdaptiveStepping>,
+    /// see ``GameContent::set_parallel_islands``, the files saved before it was there solve on one thread
+    #[serde(default)]
+    pub parallel_islands: bool,
+    /// see ``GameContent::set_deterministic``, the hash stream starts again at the loaded tick
+    #[serde(default)]
+    pub deterministic: bool,
+    /// see ``GameContent::age``
+    pub age: u32,
+    /// see ``GameContent::time``
+    pub time: f64,
+    /// length of the last step, the position based solvers need it to get the velocity back
+    pub last_step: f64,
+    /// in ``SubjectToPhysic`` order, the constraints refer to the bodies by their index in it
+    pub bodies: Vec<SavedBody>,
+    pub constraints: Vec<SavedConstraint>,
+    pub walls: Vec<SavedWall>,
+    /// warm start of the solvers, the constraints first then the contacts of the last tick
+    pub applied_correction: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBody {
+    pub position: Vector2<f64>,
+    /// see ``Position::last_tick``
+    pub last_position: Vector2<f64>,
+    pub velocity: Vector2<f64>,
+    pub acceleration: Vector2<f64>,
+    pub angle: f64,
+    pub last_angle: f64,
+    pub angular_velocity: f64,
+    pub mass: f64,
+    pub inv_mass: f64,
+    pub inertia: f64,
+    pub inv_inertia: f64,
+    pub kind: BodyKindDescription,
+    /// radius of the collider, ``None`` for bodies that don't collide
+    pub collider: Option<f64>,
+}
+
+/// A constraint and how it is solved, it is also how constraints are written in a ``crate::scene::Scene``:
+/// a rigid equality constraint only needs ``constraint``
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedConstraint {
+    pub constraint: ConstraintDescription,
+    #[serde(default = "infinity", skip_serializing_if = "is_infinite")]
+    pub stiffness: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub damping: f64,
+    #[serde(default = "equality", skip_serializing_if = "is_equality")]
+    pub bound: Bound,
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub relaxation: f64,
+}
+
+/// see ``Wall``, ``origin`` is the distance of the wall from the origin along ``normal``
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedWall {
+    pub normal: Vector2<f64>,
+    pub origin: f64,
+}
+
+impl SavedState {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateError {
+    /// the constraint at index ``constraint`` isn't a built-in one, see ``ConstraintExpression::description``
+    UndescribedConstraint { constraint: usize },
+    /// the kinematic body at index ``body`` doesn't follow a built-in trajectory, see ``Trajectory::description``
+    UndescribedTrajectory { body: usize },
+    /// no solver of the registry has this name
+    UnknownSolver(String),
+    /// the constraint at index ``constraint`` refers to a body past the saved ones, or past the bodies of the scene
+    MissingBody { constraint: usize, body: usize },
+    /// the body at index ``body`` of a scene has a mass, an inertia or a collider radius that isn't finite and positive
+    InvalidBody { body: usize, field: &'static str },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::UndescribedConstraint { constraint } => {
+                write!(f, "constraint {constraint} isn't a built-in constraint, it can't be saved")
+            }
+            StateError::UndescribedTrajectory { body } => {
+                write!(f, "body {body} doesn't follow a built-in trajectory, it can't be saved")
+            }
+            StateError::UnknownSolver(name) => write!(f, "there is no solver named {name}"),
+            StateError::MissingBody { constraint, body } => {
+                write!(f, "constraint {constraint} refers to body {body} which wasn't saved")
+            }
+            StateError::InvalidBody { body, field } => {
+                write!(f, "the {field} of body {body} must be finite and positive")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl<T: Real> GameContent<T> {
+    /// Everything needed to go on with the simulation later, see ``load_state``.
+    /// The contacts aren't saved, they are detected again on the next tick
+    pub fn save_state(&mut self) -> Result<SavedState, StateError> {
+        if self.solver_index_is_stale() {
+            self.update_solver_index();
+        }
+        let mut query = self.world.query::<(
+            &Position<T>,
+            &Velocity<T>,
+            &Acceleration<T>,
+            &Rotation<T>,
+            &AngularVelocity<T>,
+            &Mass<T>,
+            &Inertia<T>,
+            &BodyKind<T>,
+            Option<&Collider<T>>,
+        )>();
+        let view = query.view();
+        let mut bodies = Vec::with_capacity(self.physic_index_to_entity.len());
+        for (index, &e) in self.physic_index_to_entity.iter().enumerate() {
+            let (position, velocity, acceleration, rotation, angular_velocity, mass, inertia, kind, collider) =
+                view.get(e).unwrap();
+            let kind = kind
+                .description()
+                .ok_or(StateError::UndescribedTrajectory { body: index })?;
+            bodies.push(SavedBody {
+                position: position.actual.map(to_f64),
+                last_position: position.last_tick.map(to_f64),
+                velocity: velocity.0.map(to_f64),
+                acceleration: acceleration.0.map(to_f64),
+                angle: to_f64(rotation.actual),
+                last_angle: to_f64(rotation.last_tick),
+                angular_velocity: to_f64(angular_velocity.0),
+                mass: to_f64(mass.mass),
+                inv_mass: to_f64(mass.inv_mass),
+                inertia: to_f64(inertia.inertia),
+                inv_inertia: to_f64(inertia.inv_inertia),
+                kind,
+                collider: collider.map(|collider| to_f64(collider.radius)),
+            });
+        }
+
+        let mut query = self.world.query::<&SubjectToPhysic>();
+        let view = query.view();
+        let convertor = |e: Entity| view.get(e).unwrap().0;
+        let constraints = self
+            .constraints
+            .iter()
+            .enumerate()
+            .map(|(index, c)| {
+                Ok(SavedConstraint {
+                    constraint: c
+                        .expression
+                        .description(&convertor)
+                        .ok_or(StateError::UndescribedConstraint { constraint: index })?,
+                    stiffness: to_f64(c.stiffness),
+                    damping: to_f64(c.damping),
+                    bound: c.bound,
+                    relaxation: to_f64(c.relaxation),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(SavedState {
+            solver: self.solver.name().to_string(),
+            time_step: to_f64(self.time_step),
+            substeps: self.substeps,
+            gravity: self.gravity.map(to_f64),
+            adaptive_stepping: self.adaptive_stepping,
+            parallel_islands: self.parallel_islands,
+            deterministic: self.deterministic,
+            age: self.age,
+            time: to_f64(self.time),
+            last_step: to_f64(self.last_step),
+            bodies,
+            constraints,
+            walls: self
+                .walls
+                .iter()
+                .map(|wall| SavedWall {
+                    normal: wall.normal.map(to_f64),
+                    origin: to_f64(wall.origin),
+                })
+                .collect(),
+            applied_correction: self.applied_correction.iter().copied().map(to_f64).collect(),
+        })
+    }
+
+    /// Replace the scene by ``state``, the solver is looked up by name in ``solvers``.
+    /// On error nothing is changed
+    pub fn load_state(&mut self, state: &SavedState, solvers: &SolverRegistry<T>) -> Result<(), StateError> {
+        let solver = solvers
+            .get(&state.solver)
+            .ok_or_else(|| StateError::UnknownSolver(state.solver.clone()))?;
+        for (constraint, saved) in state.constraints.iter().enumerate() {
+            if let Some(body) = saved.constraint.bodies().into_iter().find(|&body| body >= state.bodies.len()) {
+                return Err(StateError::MissingBody { constraint, body });
+            }
+        }
+
+        self.clear();
+        self.solver = solver;
+        self.time_step = real(state.time_step);
+        self.set_substeps(state.substeps);
+        self.gravity = state.gravity.map(real);
+        self.adaptive_stepping = state.adaptive_stepping;
+        self.parallel_islands = state.parallel_islands;
+        self.deterministic = state.deterministic;
+        self.age = state.age;
+        self.time = real(state.time);
+        self.last_step = real(state.last_step);
+        for (index, body) in state.bodies.iter().enumerate() {
+            let entity = self.world.spawn((
+                Position::<T> {
+                    actual: body.position.map(real),
+                    last_tick: body.last_position.map(real),
+                },
+                Velocity::<T>(body.velocity.map(real)),
+                Acceleration::<T>(body.acceleration.map(real)),
+                SubjectToPhysic(index),
+                Mass::<T> {
+                    mass: real(body.mass),
+                    inv_mass: real(body.inv_mass),
+                },
+                Rotation::<T> {
+                    actual: real(body.angle),
+                    last_tick: real(body.last_angle),
+                },
+                AngularVelocity::<T>(real(body.angular_velocity)),
+                Inertia::<T> {
+                    inertia: real(body.inertia),
+                    inv_inertia: real(body.inv_inertia),
+                },
+                body.kind.body_kind::<T>(),
+            ));
+            if let Some(radius) = body.collider {
+                self.world.insert_one(entity, Collider::<T> { radius: real(radius) }).unwrap();
+            }
+            self.physic_index_to_entity.push(entity);
+        }
+        for saved in &state.constraints {
+            self.constraints.push(Constraint {
+                expression: saved.constraint.build(&self.physic_index_to_entity).unwrap(),
+                stiffness: real(saved.stiffness),
+                damping: real(saved.damping),
+                bound: saved.bound,
+                relaxation: real(saved.relaxation),
+            });
+        }
+        self.walls = state
+            .walls
+            .iter()
+            .map(|wall| Wall {
+                normal: wall.normal.map(real),
+                origin: real(wall.origin),
+            })
+            .collect();
+        self.applied_correction =
+            DVector::from_iterator(state.applied_correction.len(), state.applied_correction.iter().copied().map(real));
+        Ok(())
+    }
+}
+
+// defaults of the fields that can be left out of a scene file, they are not written either
+
+fn infinity() -> f64 {
+    f64::INFINITY
+}
+
+fn is_infinite(value: &f64) -> bool {
+    *value == f64::INFINITY
+}
+
+fn equality() -> Bound {
+    Bound::Equality
+}
+
+fn is_equality(bound: &Bound) -> bool {
+    *bound == Bound::Equality
+}
+
+pub(crate) fn one() -> f64 {
+    1.0
+}
+
+pub(crate) fn is_one(value: &f64) -> bool {
+    *value == 1.0
+}
+
+pub(crate) fn is_zero<V: Default + PartialEq>(value: &V) -> bool {
+    *value == V::default()
+}
@@ -0,0 +1,75 @@
+//! Scalar type of the simulation.
+//! Everything is generic over it so a scene can be run in single or double precision,
+//! the GUI only ever sees f32 through ``WorldSnapshot``.
+
+use nalgebra::{RealField, Vector2};
+
+pub trait Real: RealField + Copy {}
+
+impl<T: RealField + Copy> Real for T {}
+
+/// constants are written in f64 and converted to the scalar of the simulation
+pub fn real<T: Real>(value: f64) -> T {
+    nalgebra::convert(value)
+}
+
+pub fn vector<T: Real>(x: f64, y: f64) -> Vector2<T> {
+    Vector2::new(real(x), real(y))
+}
+
+pub fn to_f64<T: Real>(value: T) -> f64 {
+    value.to_subset().unwrap()
+}
+
+/// conversion for display purposes
+pub fn to_f32<T: Real>(value: T) -> f32 {
+    to_f64(value) as f32
+}
+
+pub fn vector_to_f32<T: Real>(value: Vector2<T>) -> Vector2<f32> {
+    value.map(to_f32)
+}
+
+/// Sine and cosine computed with basic arithmetic only.
+/// The ones of the standard library come from the libm of the platform, which doesn't round the same way on every
+/// architecture, so the same scene would drift apart on desktop and Android.
+/// The polynomials are the ones of fdlibm, accurate to about an ulp in f64
+pub fn sin_cos<T: Real>(value: T) -> (T, T) {
+    let x = to_f64(value);
+    if !x.is_finite() {
+        return (real(f64::NAN), real(f64::NAN));
+    }
+    // x = n π/2 + y with |y| ≤ π/4, π/2 is split in three so that n π/2 is exact for any reasonable angle
+    const PIO2_1: f64 = 1.570_796_326_734_125_6e0;
+    const PIO2_2: f64 = 6.077_100_506_303_966e-11;
+    const PIO2_3: f64 = 2.022_266_248_711_166_5e-21;
+    let n = (x * std::f64::consts::FRAC_2_PI).round();
+    let y = x - n * PIO2_1 - n * PIO2_2 - n * PIO2_3;
+
+    let z = y * y;
+    let sin = y
+        + y * z
+            * (-1.666_666_666_666_663_2e-1
+                + z * (8.333_333_333_322_49e-3
+                    + z * (-1.984_126_982_985_795e-4
+                        + z * (2.755_731_370_707_006_8e-6
+                            + z * (-2.505_076_025_340_686_3e-8 + z * 1.589_690_995_211_55e-10)))));
+    let r = z
+        * z
+        * (4.166_666_666_666_66e-2
+            + z * (-1.388_888_888_887_411e-3
+                + z * (2.480_158_728_947_673e-5
+                    + z * (-2.755_731_435_139_066_3e-7
+                        + z * (2.087_572_321_298_175e-9 + z * -1.135_964_755_778_819_5e-11)))));
+    let half_z = 0.5 * z;
+    let w = 1.0 - half_z;
+    let cos = w + (((1.0 - w) - half_z) + r);
+
+    let (sin, cos) = match (n as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    };
+    (real(sin), real(cos))
+}
@@ -0,0 +1,150 @@
+//! Scenes written as data: bodies, constraints, walls and gravity, in RON.
+//! ``GameContent::load_scene`` builds a simulation from one, so a new test scene is a new file, without any recompile.
+//! Everything that has a usual value can be left out, a pendulum is only:
+//! ```ron
+//! (
+//!     bodies: [(position: (1.0, 0.0))],
+//!     constraints: [(constraint: Anchor(body: 0, anchor: (0.0, 0.0), distance: 1.0))],
+//! )
+//! ```
+
+use crate::collision::Wall;
+use crate::components::BodyKindDescription;
+use crate::persistence::{SavedConstraint, SavedWall, StateError, is_one, is_zero, one};
+use crate::scalar::{Real, real};
+use crate::{Constraint, GameContent};
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    /// what the scene shows, for whoever picks it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default = "default_gravity")]
+    pub gravity: Vector2<f64>,
+    /// the constraints refer to the bodies by their index in it
+    #[serde(default)]
+    pub bodies: Vec<SceneBody>,
+    #[serde(default)]
+    pub constraints: Vec<SavedConstraint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub walls: Vec<SavedWall>,
+}
+
+/// a body at the beginning of the scene, the position of a kinematic body is given by its trajectory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneBody {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub position: Vector2<f64>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub angle: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub velocity: Vector2<f64>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub angular_velocity: f64,
+    /// static and kinematic bodies ignore it, their mass is infinite
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub mass: f64,
+    /// moment of inertia, without it the body is a point mass that constraints can't make spin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inertia: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_dynamic")]
+    pub kind: BodyKindDescription,
+    /// radius of the collider, ``None`` for bodies that don't collide
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collider: Option<f64>,
+}
+
+impl SceneBody {
+    /// the field that would make the simulation divide by 0 or spread NaN, if any
+    fn validate(&self) -> Result<(), &'static str> {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if self.kind == BodyKindDescription::Dynamic {
+            if !positive(self.mass) {
+                return Err("mass");
+            }
+            if self.inertia.is_some_and(|inertia| !positive(inertia)) {
+                return Err("inertia");
+            }
+        }
+        if self.collider.is_some_and(|radius| !positive(radius)) {
+            return Err("collider radius");
+        }
+        Ok(())
+    }
+}
+
+impl Scene {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+impl<T: Real> GameContent<T> {
+    /// Replace the simulation by ``scene``, the solver and the other settings are kept.
+    /// On error nothing is changed
+    pub fn load_scene(&mut self, scene: &Scene) -> Result<(), StateError> {
+        for (index, body) in scene.bodies.iter().enumerate() {
+            body.validate().map_err(|field| StateError::InvalidBody { body: index, field })?;
+        }
+        for (constraint, saved) in scene.constraints.iter().enumerate() {
+            if let Some(body) = saved.constraint.bodies().into_iter().find(|&body| body >= scene.bodies.len()) {
+                return Err(StateError::MissingBody { constraint, body });
+            }
+        }
+
+        self.clear();
+        self.gravity = scene.gravity.map(real);
+        for body in &scene.bodies {
+            let position = body.position.map(real);
+            let velocity = body.velocity.map(real);
+            let entity = match (body.kind, body.inertia) {
+                (BodyKindDescription::Dynamic, None) => self.add_body(position, velocity, real(body.mass)),
+                (BodyKindDescription::Dynamic, Some(inertia)) => self.add_rigid_body(
+                    position,
+                    real(body.angle),
+                    velocity,
+                    real(body.angular_velocity),
+                    real(body.mass),
+                    real(inertia),
+                ),
+                (BodyKindDescription::Static, _) => self.add_static_body(position, real(body.angle)),
+                (BodyKindDescription::Kinematic(trajectory), _) => {
+                    self.spawn_kinematic_body(trajectory.body_kind())
+                }
+            };
+            if let Some(radius) = body.collider {
+                self.add_collider(entity, real(radius));
+            }
+        }
+        for saved in &scene.constraints {
+            self.push_constraint(Constraint {
+                expression: saved.constraint.build(&self.physic_index_to_entity).unwrap(),
+                stiffness: real(saved.stiffness),
+                damping: real(saved.damping),
+                bound: saved.bound,
+                relaxation: real(saved.relaxation),
+            });
+        }
+        for wall in &scene.walls {
+            self.walls.push(Wall {
+                normal: wall.normal.map(real::<T>).normalize(),
+                origin: real(wall.origin / wall.normal.norm()),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn default_gravity() -> Vector2<f64> {
+    Vector2::new(0.0, -9.81)
+}
+
+fn is_dynamic(kind: &BodyKindDescription) -> bool {
+    *kind == BodyKindDescription::Dynamic
+}
@@ -0,0 +1,2119 @@
+use crate::scalar::{Real, real, to_f32};
+use crate::{Bound, Constraint};
+use crate::components::{
+    Acceleration, AngularVelocity, BodyKind, Inertia, Mass, Position, Rotation, SubjectToPhysic, Velocity,
+};
+use crate::constraints::{DOF, JacobianRow};
+use hecs::{Entity, World};
+use nalgebra::{DVector, Vector2};
+use nalgebra_sparse::factorization::CscCholesky;
+use nalgebra_sparse::{CooMatrix, CscMatrix};
+use std::fmt;
+use std::sync::Arc;
+
+/// iterations of the projected Gauss-Seidel used when unilateral constraints are present
+const PGS_MAX_ITERATIONS: usize = 256;
+/// the projected Gauss-Seidel stops once no multiplier moved more than this
+const PGS_TOLERANCE: f64 = 1e-6;
+/// the Newton iterations of ``ImplicitEuler`` stop once no velocity moved more than this
+const NEWTON_TOLERANCE: f64 = 1e-6;
+/// the line search of ``ImplicitEuler`` halves the step at most this many times
+const LINE_SEARCH_STEPS: usize = 10;
+/// the position projection of ``Rattle`` stops once no rigid constraint is violated by more than this
+const SHAKE_TOLERANCE: f64 = 1e-6;
+
+/// A global solver, it is given the assembled system at the beginning of the tick and is responsible for
+/// integrating every body of the world by one time step.
+/// Solvers are shared between the GUI and the logic thread, any configuration should be done before registering them.
+pub trait Solver<T: Real = f32>: Send + Sync {
+    /// name displayed in the GUI, it is also used to identify the solver in the registry
+    fn name(&self) -> &str;
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError>;
+}
+
+/// Why a step couldn't be completed.
+/// The world is left as the solver left it, it shouldn't be stepped again before being reset
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    /// a system that should be positive definite once the redundant rows are removed couldn't be factorised
+    Factorisation,
+    /// a position or a velocity isn't a number anymore
+    NotANumber,
+    /// an iterative method didn't converge within ``iterations``
+    IterationLimit { iterations: usize },
+    /// the constraint at index ``constraint`` depends on a body that isn't simulated
+    MissingBody { constraint: usize, body: Entity },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Factorisation => write!(f, "the constraint system couldn't be factorised"),
+            SolveError::NotANumber => write!(f, "the simulation diverged, a position or a velocity is not a number"),
+            SolveError::IterationLimit { iterations } => {
+                write!(f, "the iterative solver didn't converge in {iterations} iterations")
+            }
+            SolveError::MissingBody { constraint, body } => {
+                write!(f, "constraint {constraint} depends on {body:?} which isn't simulated")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Everything a solver needs to integrate the world by one step.
+/// Matrices are assembled from the state of the world at the beginning of the tick,
+/// if a solver moves the bodies before solving again, it must rebuild them with the provided methods.
+/// Matrices are sparse, a constraint only involves one or two bodies.
+pub struct SolverContext<'a, T: Real> {
+    /// jacobian of the constraints, one row per constraint, ``DOF`` columns per body
+    pub j: CscMatrix<T>,
+    /// diagonal matrix of the inverse masses and inverse inertia, ``DOF`` rows per body
+    pub inv_mass: CscMatrix<T>,
+    /// external forces applied to every body (gravity for now)
+    pub force: DVector<T>,
+    pub c: DVector<T>,
+    pub c_dot: DVector<T>,
+    pub world: &'a mut World,
+    pub gravity: Vector2<T>,
+    pub time_step: T,
+    /// number of substeps the tick should be split into, only the sub-stepping solvers use it
+    pub substeps: usize,
+    /// the force applied by each constraint, solvers should fill it, this is only used for display
+    pub applied_correction: &'a mut DVector<T>,
+    /// convergence of the iterative linear solves of the tick, filled by ``solve_conjugate_gradient``
+    pub iteration_stats: Option<IterationStats>,
+    /// constraints the last factorisation of K left out, filled by ``factorise`` and ``solve_lcp``.
+    /// It stays empty with the solvers that never factorise K
+    pub redundant_constraints: Vec<usize>,
+    constraints: Vec<&'a Constraint<T>>,
+    physic_index_to_entity: &'a [Entity],
+}
+
+impl<'a, T: Real> SolverContext<'a, T> {
+    pub(super) fn new(
+        world: &'a mut World,
+        constraints: Vec<&'a Constraint<T>>,
+        physic_index_to_entity: &'a [Entity],
+        applied_correction: &'a mut DVector<T>,
+        gravity: Vector2<T>,
+        time_step: T,
+        substeps: usize,
+    ) -> Self {
+        let mut ctx = Self {
+            j: CscMatrix::zeros(0, 0),
+            inv_mass: CscMatrix::zeros(0, 0),
+            force: DVector::zeros(0),
+            c: DVector::zeros(0),
+            c_dot: DVector::zeros(0),
+            world,
+            gravity,
+            time_step,
+            substeps,
+            applied_correction,
+            iteration_stats: None,
+            redundant_constraints: Vec::new(),
+            constraints,
+            physic_index_to_entity,
+        };
+        ctx.j = ctx.j_matrix();
+        ctx.inv_mass = ctx.inv_mass_matrix();
+        ctx.force = ctx.force_vector();
+        ctx.c = ctx.c_vector();
+        ctx.c_dot = ctx.c_dot_vector();
+        ctx
+    }
+
+    /// euler integration of the gravity over ``time_step``, only the dynamic bodies fall
+    pub fn apply_gravity(&self, time_step: T) {
+        for (_, (velocity, kind)) in self.world.query::<(&mut Velocity<T>, &BodyKind<T>)>().iter() {
+            if kind.is_dynamic() {
+                velocity.0 += self.gravity * time_step;
+            }
+        }
+    }
+
+    /// q_dot is the combined velocity of all bodies
+    pub fn q_dot_vector(&self) -> DVector<T> {
+        let mut query = self.world.query::<(&Velocity<T>, &AngularVelocity<T>)>();
+        let view = query.view();
+        let size = self.physic_index_to_entity.len() * DOF;
+        DVector::from_iterator(
+            size,
+            self.physic_index_to_entity.iter().cloned().flat_map(|e| {
+                let (velocity, angular_velocity) = view.get(e).unwrap();
+                [velocity.x, velocity.y, angular_velocity.0].into_iter()
+            }),
+        )
+    }
+
+    /// q is the combined position of all bodies: x, y and the angle
+    pub fn q_vector(&self) -> DVector<T> {
+        let mut query = self.world.query::<(&Position<T>, &Rotation<T>)>();
+        let view = query.view();
+        let size = self.physic_index_to_entity.len() * DOF;
+        DVector::from_iterator(
+            size,
+            self.physic_index_to_entity.iter().cloned().flat_map(|e| {
+                let (position, rotation) = view.get(e).unwrap();
+                [position.x, position.y, rotation.actual].into_iter()
+            }),
+        )
+    }
+
+    /// move every body to ``q``, see ``q_vector``
+    pub fn set_q_vector(&mut self, q: &DVector<T>) {
+        for (i, &e) in self.physic_index_to_entity.iter().enumerate() {
+            let (position, rotation) = self
+                .world
+                .query_one_mut::<(&mut Position<T>, &mut Rotation<T>)>(e)
+                .unwrap();
+            position.actual = linear_part(q, i);
+            rotation.actual = angular_part(q, i);
+        }
+    }
+
+    pub fn j_matrix(&self) -> CscMatrix<T> {
+        let mut query = self.world.query::<(&Position<T>, &Rotation<T>, &SubjectToPhysic)>();
+        let view = query.view();
+        let mut j = CooMatrix::new(self.constraints.len(), self.physic_index_to_entity.len() * DOF);
+        let mut row = JacobianRow::default();
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            row.clear();
+            constraint.expression.build_j_row(&view, &mut row);
+            for &(column, value) in row.entries() {
+                j.push(i, column, value);
+            }
+        }
+        CscMatrix::from(&j)
+    }
+
+    pub fn inv_mass_matrix(&self) -> CscMatrix<T> {
+        let mut query = self.world.query::<(&Mass<T>, &Inertia<T>)>();
+        let view = query.view();
+        let iter = self.physic_index_to_entity.iter().cloned().flat_map(|e| {
+            let (mass, inertia) = view.get(e).unwrap();
+            [mass.inv_mass, mass.inv_mass, inertia.inv_inertia].into_iter()
+        });
+        diagonal_matrix(iter)
+    }
+
+    pub fn force_vector(&self) -> DVector<T> {
+        let mut query = self.world.query::<(&Mass<T>, &BodyKind<T>)>();
+        let view = query.view();
+        let size = self.physic_index_to_entity.len() * DOF;
+        let iter = self.physic_index_to_entity.iter().cloned().flat_map(|e| {
+            let (mass, kind) = view.get(e).unwrap();
+            // static and kinematic bodies have an infinite mass, nothing moves them
+            let gravity = if kind.is_dynamic() { self.gravity * mass.mass } else { Vector2::zeros() };
+            // gravity doesn't apply any torque
+            [gravity.x, gravity.y, T::zero()].into_iter()
+        });
+        DVector::from_iterator(size, iter)
+    }
+
+    pub fn j_dot_q_dot(&self) -> DVector<T> {
+        let mut query = self
+            .world
+            .query::<(&Position<T>, &Rotation<T>, &Velocity<T>, &AngularVelocity<T>)>();
+        let view = query.view();
+        DVector::from_iterator(
+            self.constraints.len(),
+            self.constraints
+                .iter()
+                .map(|c| c.expression.compute_j_dot_q_dot(&view)),
+        )
+    }
+
+    pub fn compute_ddot_q_dot_plus_j_dot_q_ddot(&self) -> DVector<T> {
+        for (_, (acceleration, kind)) in self.world.query::<(&mut Acceleration<T>, &BodyKind<T>)>().iter() {
+            // the acceleration of kinematic bodies comes from their trajectory, the dynamic ones fall freely,
+            // whatever their mass
+            if kind.is_dynamic() {
+                acceleration.0 = self.gravity;
+            }
+        }
+
+        let mut query = self.world.query::<(
+            &Position<T>,
+            &Rotation<T>,
+            &Velocity<T>,
+            &AngularVelocity<T>,
+            &Acceleration<T>,
+        )>();
+        let view = query.view();
+        DVector::from_iterator(
+            self.constraints.len(),
+            self.constraints
+                .iter()
+                .map(|c| c.expression.compute_ddot_q_dot_plus_j_dot_q_ddot(&view)),
+        )
+    }
+
+    pub fn c_dot_vector(&self) -> DVector<T> {
+        let mut query = self
+            .world
+            .query::<(&Position<T>, &Rotation<T>, &Velocity<T>, &AngularVelocity<T>)>();
+        let view = query.view();
+        let len = self.constraints.len();
+        DVector::from_iterator(
+            len,
+            self.constraints.iter().map(|c| c.expression.evaluate_c_dot(&view)),
+        )
+    }
+
+    pub fn c_vector(&self) -> DVector<T> {
+        let mut query = self.world.query::<(&Position<T>, &Rotation<T>)>();
+        let view = query.view();
+        let len = self.constraints.len();
+        DVector::from_iterator(
+            len,
+            self.constraints.iter().map(|c| {
+                c.expression.evaluate_c(&view)
+            }),
+        )
+    }
+
+    pub fn gamma_matrix(&self) -> CscMatrix<T> {
+        diagonal_matrix(self.constraints.iter().map(|constraint| {
+            (constraint.damping + self.time_step * constraint.stiffness).recip()
+        }))
+    }
+
+    pub fn beta_c_over_h_vector(&self) -> DVector<T> {
+        let mut c = self.c_vector();
+        c.iter_mut().enumerate().for_each(|(i, c)| {
+            let constraint = &self.constraints[i];
+            let beta = if !constraint.stiffness.is_finite() {
+                T::one()
+            }
+            else {
+                (self.time_step * constraint.stiffness) / (constraint.damping + self.time_step * constraint.stiffness)
+            };
+
+
+            *c *= beta/self.time_step;
+            // a slack unilateral constraint must not be pulled back to 0
+            if constraint.bound.is_inactive(*c) {
+                *c = T::zero();
+            }
+        });
+        c
+    }
+
+    pub fn has_unilateral(&self) -> bool {
+        self.constraints.iter().any(|c| c.bound != Bound::Equality)
+    }
+
+    /// how fast a slack unilateral constraint may close during this step, C/h for inactive rows and 0 otherwise.
+    /// velocity level solvers subtract it from their right hand side, so a body may come closer to a floor
+    /// without reaching it, instead of being stopped where it stands
+    pub fn velocity_slack(&self) -> DVector<T> {
+        DVector::from_iterator(
+            self.constraints.len(),
+            self.constraints.iter().zip(self.c.iter()).map(|(constraint, &c)| {
+                if constraint.bound.is_inactive(c) { c / self.time_step } else { T::zero() }
+            }),
+        )
+    }
+
+    /// same as ``velocity_slack`` for force level solvers, inactive rows get an infinite slack
+    /// which ``solve_lcp`` understands as "this constraint doesn't apply any force"
+    pub fn force_slack(&self) -> DVector<T> {
+        DVector::from_iterator(
+            self.constraints.len(),
+            self.constraints.iter().zip(self.c.iter()).map(|(constraint, &c)| {
+                if !constraint.bound.is_inactive(c) {
+                    T::zero()
+                } else if constraint.bound == Bound::Push {
+                    real(f64::INFINITY)
+                } else {
+                    real(f64::NEG_INFINITY)
+                }
+            }),
+        )
+    }
+
+    /// factorise ``k``, a system with one row per constraint like K = J * M^-1 * J^T,
+    /// and keep the rows it left out in ``redundant_constraints``
+    pub fn factorise(&mut self, k: &CscMatrix<T>) -> Result<Factorisation<T>, SolveError> {
+        let factorisation = Factorisation::new(k)?;
+        self.redundant_constraints = factorisation.redundant().to_vec();
+        Ok(factorisation)
+    }
+
+    fn bounds(&self) -> Vec<Bound> {
+        self.constraints.iter().map(|c| c.bound).collect()
+    }
+
+    /// solve K * lambda = b while respecting the bound of every constraint.
+    /// Without unilateral constraint this is a plain Cholesky solve (see ``Factorisation`` for redundant constraints),
+    /// otherwise the linear complementarity problem is solved with a projected Gauss-Seidel starting from 0.
+    /// The unbounded solution isn't used as a guess, piles of contacts are often redundant and it can be huge.
+    /// Rows with an infinite right hand side are kept at 0
+    pub fn solve_lcp(&mut self, k: CscMatrix<T>, b: &DVector<T>) -> Result<DVector<T>, SolveError> {
+        if !self.has_unilateral() {
+            return Ok(self.factorise(&k)?.solve(b));
+        }
+        let (lambda, stats) = projected_gauss_seidel(&k, b, &self.bounds());
+        self.add_iteration_stats(stats);
+        Ok(lambda)
+    }
+
+    /// solve K * lambda = b with the conjugate gradient, starting from ``guess``, and add the iterations to
+    /// ``iteration_stats``. The conjugate gradient doesn't know about bounds, with unilateral constraints this
+    /// falls back to ``solve_lcp``
+    pub fn solve_conjugate_gradient(
+        &mut self,
+        settings: &ConjugateGradient,
+        k: CscMatrix<T>,
+        b: &DVector<T>,
+        guess: DVector<T>,
+    ) -> Result<DVector<T>, SolveError> {
+        if self.has_unilateral() {
+            return self.solve_lcp(k, b);
+        }
+        let (lambda, iterations, residual) = settings.solve(&k, b, guess)?;
+        self.add_iteration_stats(IterationStats::single(iterations, residual, true));
+        Ok(lambda)
+    }
+
+    /// merge the convergence of some solves into ``iteration_stats``
+    pub fn add_iteration_stats(&mut self, stats: IterationStats) {
+        self.iteration_stats.get_or_insert_with(IterationStats::default).merge(stats);
+    }
+}
+
+/// solve K * lambda = b with ``lambda[i]`` respecting ``bounds[i]``, starting from 0.
+/// Rows with an infinite right hand side are kept at 0.
+/// A pile of contacts can need more than ``PGS_MAX_ITERATIONS``, the multipliers are returned anyway
+/// and the stats tell they didn't converge
+pub fn projected_gauss_seidel<T: Real>(
+    k: &CscMatrix<T>,
+    b: &DVector<T>,
+    bounds: &[Bound],
+) -> (DVector<T>, IterationStats) {
+    let len = b.len();
+    let diagonals: Vec<T> = (0..len)
+        .map(|i| k.get_entry(i, i).map_or(T::zero(), |entry| entry.into_value()))
+        .collect();
+
+    let mut lambda = DVector::<T>::zeros(len);
+
+    let mut max_delta = T::zero();
+    for iteration in 1..=PGS_MAX_ITERATIONS {
+        max_delta = T::zero();
+        for i in 0..len {
+            let diagonal = diagonals[i];
+            if !b[i].is_finite() || diagonal <= T::zero() {
+                continue;
+            }
+            // K is symmetric, the column is the row
+            let column = k.col(i);
+            let k_lambda: T = column
+                .row_indices()
+                .iter()
+                .zip(column.values())
+                .fold(T::zero(), |sum, (&row, &value)| sum + value * lambda[row]);
+            let residual = b[i] - k_lambda;
+            let updated = bounds[i].clamp(lambda[i] + residual / diagonal);
+            max_delta = max_delta.max((updated - lambda[i]).abs());
+            lambda[i] = updated;
+        }
+        if max_delta < real(PGS_TOLERANCE) {
+            return (lambda, IterationStats::single(iteration, max_delta, true));
+        }
+    }
+    (lambda, IterationStats::single(PGS_MAX_ITERATIONS, max_delta, false))
+}
+
+/// Preconditioner of the conjugate gradient, it approximates K^-1 to reduce the number of iterations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preconditioner {
+    None,
+    /// the inverse of the diagonal, almost free
+    Jacobi,
+    /// a Cholesky factorisation that keeps the sparsity of K, IC(0)
+    IncompleteCholesky,
+}
+
+/// Settings of the conjugate gradient, see ``SolverContext::solve_conjugate_gradient``
+#[derive(Debug, Clone, Copy)]
+pub struct ConjugateGradient {
+    /// the iterations stop once the norm of the residual b - K * lambda is below this
+    pub tolerance: f64,
+    /// ``SolveError::IterationLimit`` past this many iterations
+    pub max_iterations: usize,
+    pub preconditioner: Preconditioner,
+}
+
+impl Default for ConjugateGradient {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-6,
+            max_iterations: 1000,
+            preconditioner: Preconditioner::Jacobi,
+        }
+    }
+}
+
+/// Convergence of the iterative solves of one tick, reported in ``WorldSnapshot``
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IterationStats {
+    /// number of linear systems solved
+    pub solves: usize,
+    /// iterations of every solve
+    pub iterations: usize,
+    /// largest residual the solves ended with: the norm of b - K * lambda for the conjugate gradient,
+    /// the last change of a multiplier for the projected Gauss-Seidel, the last update of the velocity for the
+    /// Newton iterations of ``ImplicitEuler``, the largest violation for the SHAKE iterations of ``Rattle``
+    pub residual: f32,
+    /// solves that stopped at their iteration cap before reaching their tolerance, their result is used anyway
+    pub unconverged: usize,
+}
+
+impl IterationStats {
+    /// the stats of one solve
+    pub fn single<T: Real>(iterations: usize, residual: T, converged: bool) -> Self {
+        Self {
+            solves: 1,
+            iterations,
+            residual: to_f32(residual),
+            unconverged: usize::from(!converged),
+        }
+    }
+
+    pub fn merge(&mut self, other: IterationStats) {
+        self.solves += other.solves;
+        self.iterations += other.iterations;
+        self.residual = self.residual.max(other.residual);
+        self.unconverged += other.unconverged;
+    }
+}
+
+impl ConjugateGradient {
+    /// the solution, the number of iterations and the norm of the last residual
+    fn solve<T: Real>(
+        &self,
+        k: &CscMatrix<T>,
+        b: &DVector<T>,
+        guess: DVector<T>,
+    ) -> Result<(DVector<T>, usize, T), SolveError> {
+        let preconditioner = PreconditionerMatrix::new(self.preconditioner, k);
+        let tolerance = real::<T>(self.tolerance);
+        let mut lambda = guess;
+        let mut r = b - k * &lambda;
+        let mut z = preconditioner.apply(&r);
+        let mut p = z.clone();
+        let mut r_dot_z = r.dot(&z);
+        let mut iteration = 0;
+        loop {
+            let residual = r.norm();
+            if !residual.is_finite() {
+                return Err(SolveError::NotANumber);
+            }
+            if residual < tolerance {
+                return Ok((lambda, iteration, residual));
+            }
+            if iteration == self.max_iterations {
+                return Err(SolveError::IterationLimit { iterations: iteration });
+            }
+            let k_p = k * &p;
+            let curvature = p.dot(&k_p);
+            if curvature <= T::zero() {
+                // K is singular and what is left of the residual is out of its range: redundant constraints that
+                // disagree, like a chain stretched between two anchors. Nothing better can be found
+                return Ok((lambda, iteration, residual));
+            }
+            let alpha = r_dot_z / curvature;
+            lambda += &p * alpha;
+            r -= k_p * alpha;
+            z = preconditioner.apply(&r);
+            let next_r_dot_z = r.dot(&z);
+            p = &z + &p * (next_r_dot_z / r_dot_z);
+            r_dot_z = next_r_dot_z;
+            iteration += 1;
+        }
+    }
+}
+
+/// M^-1 of the preconditioned conjugate gradient
+enum PreconditionerMatrix<T: Real> {
+    Identity,
+    /// inverse of the diagonal of K
+    Jacobi(DVector<T>),
+    /// K ~ L * L^T, ``rows[i]`` holds the entries of L left of the diagonal on row i, by increasing column
+    IncompleteCholesky { rows: Vec<Vec<(usize, T)>>, diagonal: Vec<T> },
+}
+
+impl<T: Real> PreconditionerMatrix<T> {
+    fn new(preconditioner: Preconditioner, k: &CscMatrix<T>) -> Self {
+        let diagonal = |i: usize| k.get_entry(i, i).map_or(T::zero(), |entry| entry.into_value());
+        match preconditioner {
+            Preconditioner::None => Self::Identity,
+            // a constraint that can't move anything has a zero diagonal, it is left untouched
+            Preconditioner::Jacobi => Self::Jacobi(DVector::from_iterator(
+                k.nrows(),
+                (0..k.nrows()).map(|i| if diagonal(i) > T::zero() { T::one() / diagonal(i) } else { T::one() }),
+            )),
+            Preconditioner::IncompleteCholesky => {
+                // K is symmetric, column i holds the row i
+                let mut rows: Vec<Vec<(usize, T)>> = (0..k.nrows())
+                    .map(|i| {
+                        let column = k.col(i);
+                        column
+                            .row_indices()
+                            .iter()
+                            .zip(column.values())
+                            .filter(|&(&j, _)| j < i)
+                            .map(|(&j, &value)| (j, value))
+                            .collect()
+                    })
+                    .collect();
+                let mut pivots: Vec<T> = Vec::with_capacity(k.nrows());
+                for i in 0..k.nrows() {
+                    for entry in 0..rows[i].len() {
+                        let (j, value) = rows[i][entry];
+                        let product = sparse_dot(&rows[i][..entry], &rows[j]);
+                        rows[i][entry].1 = (value - product) / pivots[j];
+                    }
+                    let square = diagonal(i) - rows[i].iter().fold(T::zero(), |sum, &(_, l)| sum + l * l);
+                    // dropping the fill-in can break the factorisation, and redundant constraints make K singular:
+                    // a pivot that vanishes falls back to the diagonal
+                    let pivot = if square > diagonal(i) * rank_tolerance() {
+                        square.sqrt()
+                    } else if diagonal(i) > T::zero() {
+                        diagonal(i).sqrt()
+                    } else {
+                        T::one()
+                    };
+                    pivots.push(pivot);
+                }
+                Self::IncompleteCholesky {
+                    rows,
+                    diagonal: pivots,
+                }
+            }
+        }
+    }
+
+    fn apply(&self, r: &DVector<T>) -> DVector<T> {
+        match self {
+            Self::Identity => r.clone(),
+            Self::Jacobi(inverse) => r.component_mul(inverse),
+            Self::IncompleteCholesky { rows, diagonal } => {
+                // L * y = r
+                let mut y = r.clone();
+                for (i, row) in rows.iter().enumerate() {
+                    let sum = row.iter().fold(T::zero(), |sum, &(j, l)| sum + l * y[j]);
+                    y[i] = (y[i] - sum) / diagonal[i];
+                }
+                // L^T * z = y, L^T is walked by columns
+                for (i, row) in rows.iter().enumerate().rev() {
+                    y[i] /= diagonal[i];
+                    let z = y[i];
+                    for &(j, l) in row {
+                        y[j] -= l * z;
+                    }
+                }
+                y
+            }
+        }
+    }
+}
+
+/// dot product of two sparse rows sorted by column
+fn sparse_dot<T: Real>(a: &[(usize, T)], b: &[(usize, T)]) -> T {
+    let (mut i, mut j, mut sum) = (0, 0, T::zero());
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                sum += a[i].1 * b[j].1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    sum
+}
+
+/// square sparse matrix with the given diagonal
+fn diagonal_matrix<T: Real>(diagonal: impl Iterator<Item = T>) -> CscMatrix<T> {
+    let values: Vec<T> = diagonal.collect();
+    let size = values.len();
+    CscMatrix::try_from_csc_data(size, size, (0..=size).collect(), (0..size).collect(), values)
+        .unwrap()
+}
+
+/// the sparse factorisation works with matrices, the right hand side is a single column
+pub fn cholesky_solve<T: Real>(cholesky: &CscCholesky<T>, b: &DVector<T>) -> DVector<T> {
+    cholesky.solve(b).column(0).into_owned()
+}
+
+/// Factorisation of a symmetric positive semi-definite matrix like K = J * M^-1 * J^T.
+/// K is singular when some constraints are redundant (a truss with more bars than needed, a fully stretched chain,
+/// a constraint whose bodies can't move along it). Those rows are detected and left out: their multiplier is 0
+/// and the other rows are solved exactly
+pub struct Factorisation<T: Real> {
+    /// factorisation of the independent rows
+    cholesky: Option<CscCholesky<T>>,
+    /// rows left out of the solve, in increasing order
+    redundant: Vec<usize>,
+    size: usize,
+}
+
+impl<T: Real> Factorisation<T> {
+    pub fn new(k: &CscMatrix<T>) -> Result<Self, SolveError> {
+        let size = k.nrows();
+        if k.values().iter().any(|value| !value.is_finite()) {
+            return Err(SolveError::NotANumber);
+        }
+        if let Ok(cholesky) = CscCholesky::factor(k) {
+            let l = cholesky.l();
+            let independent = (0..size).all(|i| {
+                let pivot = l.get_entry(i, i).map_or(T::zero(), |entry| entry.into_value());
+                let diagonal = k.get_entry(i, i).map_or(T::zero(), |entry| entry.into_value());
+                pivot * pivot > diagonal * rank_tolerance()
+            });
+            if independent {
+                return Ok(Self {
+                    cholesky: Some(cholesky),
+                    redundant: Vec::new(),
+                    size,
+                });
+            }
+        }
+
+        // J * M^-1 * J^T can only have a negative diagonal if a mass or an inertia is negative, no row is redundant
+        // with itself and leaving them all out would hide the mistake
+        if (0..size).any(|i| k.get_entry(i, i).is_some_and(|entry| entry.into_value() < T::zero())) {
+            return Err(SolveError::Factorisation);
+        }
+
+        // a pile of contacts can be so close to singular that the rounding of the factorisation makes a pivot
+        // negative where the detection found it positive, the detection is done again with a looser tolerance
+        let mut tolerance = rank_tolerance::<T>();
+        while tolerance < real(1e-3) {
+            let redundant = redundant_rows(k, tolerance);
+            let independent: Vec<usize> = (0..size).filter(|i| redundant.binary_search(i).is_err()).collect();
+            if independent.is_empty() {
+                return Ok(Self {
+                    cholesky: None,
+                    redundant,
+                    size,
+                });
+            }
+            let reduced = select_rows(&select_rows(k, &independent).transpose(), &independent);
+            if let Ok(cholesky) = CscCholesky::factor(&reduced) {
+                return Ok(Self {
+                    cholesky: Some(cholesky),
+                    redundant,
+                    size,
+                });
+            }
+            tolerance *= real(1e3);
+        }
+        Err(SolveError::Factorisation)
+    }
+
+    pub fn solve(&self, b: &DVector<T>) -> DVector<T> {
+        let Some(cholesky) = &self.cholesky else {
+            return DVector::zeros(self.size);
+        };
+        if self.redundant.is_empty() {
+            return cholesky_solve(cholesky, b);
+        }
+        let independent: Vec<usize> = (0..self.size)
+            .filter(|i| self.redundant.binary_search(i).is_err())
+            .collect();
+        let reduced_b = DVector::from_iterator(independent.len(), independent.iter().map(|&i| b[i]));
+        let reduced = cholesky_solve(cholesky, &reduced_b);
+        let mut lambda = DVector::zeros(self.size);
+        for (&i, &value) in independent.iter().zip(reduced.iter()) {
+            lambda[i] = value;
+        }
+        lambda
+    }
+
+    /// rows that are a combination of the rows before them
+    pub fn redundant(&self) -> &[usize] {
+        &self.redundant
+    }
+}
+
+/// a pivot smaller than this fraction of its diagonal is rounding noise, the row is a combination of the previous ones
+fn rank_tolerance<T: Real>() -> T {
+    T::default_epsilon() * real(1e3)
+}
+
+/// Sparse up-looking LDL^T of K, in the order of the rows, row ``i`` of L is found by solving with the rows before it.
+/// A row whose pivot is below ``tolerance`` times its diagonal is a combination of the rows before it, it is dropped
+/// instead of dividing by 0 and the rows after it are factorised as if it wasn't there.
+/// The elimination tree is built along the way, it gives the pattern of each row without a dense copy of K
+fn redundant_rows<T: Real>(k: &CscMatrix<T>, tolerance: T) -> Vec<usize> {
+    let size = k.nrows();
+    // columns of L, below the diagonal
+    let mut columns: Vec<Vec<(usize, T)>> = vec![Vec::new(); size];
+    let mut d = vec![T::zero(); size];
+    let mut parent: Vec<Option<usize>> = vec![None; size];
+    let mut is_redundant = vec![false; size];
+    // the row being factorised, scattered, and the columns it has an entry in
+    let mut y = vec![T::zero(); size];
+    let mut visited = vec![usize::MAX; size];
+    let mut pattern = Vec::new();
+    let mut path = Vec::new();
+    let mut redundant = Vec::new();
+    for row in 0..size {
+        visited[row] = row;
+        pattern.clear();
+        let mut diagonal = T::zero();
+        let column = k.col(row);
+        for (&i, &value) in column.row_indices().iter().zip(column.values()) {
+            if i > row {
+                continue;
+            }
+            if i == row {
+                diagonal = value;
+            }
+            y[i] += value;
+            // climb the elimination tree up to the rows already in the pattern, the path is pushed from the top
+            // so that the pattern is in an order where every column comes after those that update it
+            let mut node = i;
+            path.clear();
+            while visited[node] != row {
+                path.push(node);
+                visited[node] = row;
+                node = *parent[node].get_or_insert(row);
+            }
+            pattern.extend(path.drain(..).rev());
+        }
+
+        let mut pivot = y[row];
+        y[row] = T::zero();
+        let mut entries = Vec::new();
+        // the pattern was filled from the end of the tree paths, the columns are used from the last one pushed
+        for &i in pattern.iter().rev() {
+            let value = y[i];
+            y[i] = T::zero();
+            if is_redundant[i] {
+                continue;
+            }
+            for &(below, l) in &columns[i] {
+                y[below] -= l * value;
+            }
+            let l = value / d[i];
+            pivot -= l * value;
+            entries.push((i, l));
+        }
+
+        if pivot <= diagonal * tolerance {
+            is_redundant[row] = true;
+            redundant.push(row);
+            continue;
+        }
+        d[row] = pivot;
+        for (i, l) in entries {
+            columns[i].push((row, l));
+        }
+    }
+    redundant
+}
+
+/// linear part of a vector of generalized coordinates for the body at ``index``
+pub fn linear_part<T: Real>(vector: &DVector<T>, index: usize) -> Vector2<T> {
+    Vector2::new(vector[index * DOF], vector[index * DOF + 1])
+}
+
+/// angular part of a vector of generalized coordinates for the body at ``index``
+pub fn angular_part<T: Real>(vector: &DVector<T>, index: usize) -> T {
+    vector[index * DOF + 2]
+}
+
+/// the ending of most velocity level solvers
+fn correct_velocity_and_integrate<T: Real>(ctx: &mut SolverContext<T>, applied_momentum: &DVector<T>) {
+    for (_, (pos, velocity, mass, rot, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+        .world
+        .query::<(&mut Position<T>, &mut Velocity<T>, &Mass<T>, &mut Rotation<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+        .iter()
+    {
+        velocity.0 += linear_part(applied_momentum, i) * mass.inv_mass;
+        pos.actual += velocity.0 * ctx.time_step;
+        angular_velocity.0 += angular_part(applied_momentum, i) * inertia.inv_inertia;
+        rot.actual += angular_velocity.0 * ctx.time_step;
+    }
+}
+
+/// Every solver available to the GUI, in the order they are displayed.
+/// Out-of-tree solvers can be registered next to the built-in ones to compare them.
+#[derive(Clone)]
+pub struct SolverRegistry<T: Real = f32> {
+    solvers: Vec<Arc<dyn Solver<T>>>,
+}
+
+impl<T: Real> SolverRegistry<T> {
+    pub fn empty() -> Self {
+        Self { solvers: Vec::new() }
+    }
+
+    pub fn register(&mut self, solver: impl Solver<T> + 'static) {
+        self.solvers.push(Arc::new(solver));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Solver<T>>> {
+        self.solvers.iter().find(|s| s.name() == name).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Solver<T>>> {
+        self.solvers.iter()
+    }
+}
+
+impl<T: Real> Default for SolverRegistry<T> {
+    /// registry containing all the built-in solvers
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(FirstOrder);
+        registry.register(SecondOrder);
+        registry.register(FirstOrderWithPrepass);
+        registry.register(HybridV2);
+        registry.register(HybridV3);
+        registry.register(HybridV3cgm::new(ConjugateGradient {
+            preconditioner: Preconditioner::None,
+            ..ConjugateGradient::default()
+        }));
+        registry.register(HybridV3cgm::new(ConjugateGradient::default()));
+        registry.register(HybridV3cgm::new(ConjugateGradient {
+            preconditioner: Preconditioner::IncompleteCholesky,
+            ..ConjugateGradient::default()
+        }));
+        registry.register(HybridV4);
+        registry.register(Pbd);
+        registry.register(HybridV3Pbd);
+        registry.register(FirstOrderSoft);
+        registry.register(HybridV3Soft);
+        registry.register(SequentialImpulse::new(8, true));
+        registry.register(Tgs);
+        registry.register(Xpbd::new(10));
+        registry.register(ImplicitEuler::new(8));
+        registry.register(Rattle::new(16));
+        registry
+    }
+}
+
+/// this solver is fine most of the time, but fail we enter a "too wrong" state, mainly where acceleration is too high and needs to be damped, at least that was my theory back then
+pub struct FirstOrder;
+
+impl<T: Real> Solver<T> for FirstOrder {
+    fn name(&self) -> &str {
+        "FirstOrder"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let k = j * &ctx.inv_mass * &jt;
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+
+        let c = &ctx.c;
+
+        let b = -j * q_dot - c * (real::<T>(0.0) / ctx.time_step) - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        *ctx.applied_correction = &lambda / ctx.time_step;
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+/// this solver tends handle a bit better the case but is unable to maintain high rigidity such as the first order solver
+pub struct SecondOrder;
+
+impl<T: Real> Solver<T> for SecondOrder {
+    fn name(&self) -> &str {
+        "SecondOrder"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        // Here we are solving for JWJt * lambda = -J * M^-1 * F - J_dot_q_dot as described by Andrew Witkin
+        let inv_mass_matrix = &ctx.inv_mass;
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let k = j * inv_mass_matrix * &jt;
+        let force = &ctx.force;
+        let j_w_q2dot = j * inv_mass_matrix * force;
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+        let c_dot = &ctx.c_dot;
+        let c = &ctx.c;
+
+        let b = -j_w_q2dot
+            - j_dot_q_dot
+            - c_dot * (real::<T>(0.0) / ctx.time_step)
+            - c * (real::<T>(0.0) / (ctx.time_step * ctx.time_step))
+            - ctx.force_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        let applied_force = (jt * &lambda) + &ctx.force;
+        *ctx.applied_correction = lambda; // this solver is working with force, so we got what we want
+
+        //integrate velocity and position
+        for (_, (pos, velocity, mass, rot, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+            .world
+            .query::<(&mut Position<T>, &mut Velocity<T>, &Mass<T>, &mut Rotation<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+            .iter()
+        {
+            let acceleration = linear_part(&applied_force, i) * mass.inv_mass;
+            velocity.0 += acceleration * ctx.time_step;
+            pos.actual +=
+                velocity.0 * ctx.time_step + acceleration * (real::<T>(0.5) * ctx.time_step * ctx.time_step);
+            let angular_acceleration = angular_part(&applied_force, i) * inertia.inv_inertia;
+            angular_velocity.0 += angular_acceleration * ctx.time_step;
+            rot.actual += angular_velocity.0 * ctx.time_step
+                + real::<T>(0.5) * ctx.time_step * ctx.time_step * angular_acceleration;
+            // verlet integration is in appropriate here since approximation on the velocity vector is too dirty
+        }
+        Ok(())
+    }
+}
+
+/// So, new guess, I'll preprocess applied forces via a second order solver, and then use the first order solver to correct the velocity and apply Baumgarte stabilization
+pub struct FirstOrderWithPrepass;
+
+impl<T: Real> Solver<T> for FirstOrderWithPrepass {
+    fn name(&self) -> &str {
+        "FirstOrderWithPrepass"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        // both solvers work in a similar way, and can share a lot of calculations
+        // the most expensive is by far the factorisation of the K matrix, which is luckily common to both
+        let k = &ctx.j * &ctx.inv_mass * ctx.j.transpose();
+        // with unilateral constraints the system changes with the active set, so it can't be factorised once
+        let factorisation = if ctx.has_unilateral() { None } else { Some(ctx.factorise(&k)?) };
+        let bounds = ctx.bounds();
+        let mut stats = None;
+        let mut solve = |b: DVector<T>| match &factorisation {
+            Some(factorisation) => factorisation.solve(&b),
+            None => {
+                let (lambda, solve_stats) = projected_gauss_seidel(&k, &b, &bounds);
+                stats.get_or_insert_with(IterationStats::default).merge(solve_stats);
+                lambda
+            }
+        };
+        let inv_mass_matrix = &ctx.inv_mass;
+        let j = &ctx.j;
+        let jt = j.transpose();
+
+        // force pass
+        let force = &ctx.force;
+        let j_w_q2dot = j * inv_mass_matrix * force;
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+        let c_dot = &ctx.c_dot;
+        let c = &ctx.c;
+        let b = -j_w_q2dot
+            - j_dot_q_dot
+            - c_dot * (real::<T>(0.0) / ctx.time_step)
+            - c * (real::<T>(0.0) / (ctx.time_step * ctx.time_step))
+            - ctx.force_slack();
+        let lambda = solve(b);
+        let applied_acceleration = (&jt * &lambda) + force;
+
+        // velocity pass
+        for (_, (velocity, mass, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+            .world
+            .query::<(&mut Velocity<T>, &Mass<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+            .iter()
+        {
+            let acceleration = linear_part(&applied_acceleration, i) * mass.inv_mass;
+            // euler integration
+            velocity.0 += acceleration * ctx.time_step;
+            angular_velocity.0 +=
+                angular_part(&applied_acceleration, i) * inertia.inv_inertia * ctx.time_step;
+        }
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+
+        let b = -j * q_dot - c * (real::<T>(0.0) / ctx.time_step) - ctx.velocity_slack();
+        let lambda = solve(b);
+        let applied_momentum = jt * &lambda;
+        if let Some(stats) = stats {
+            ctx.add_iteration_stats(stats);
+        }
+
+        // correct the velocity and integrate position
+        for (_, (pos, velocity, mass, rot, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+            .world
+            .query::<(&mut Position<T>, &mut Velocity<T>, &Mass<T>, &mut Rotation<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+            .iter()
+        {
+            velocity.0 += linear_part(&applied_momentum, i) * mass.inv_mass;
+            let acceleration = linear_part(&applied_acceleration, i) * mass.inv_mass;
+            // euler integration
+            pos.actual +=
+                velocity.0 * ctx.time_step + acceleration * real::<T>(0.5) * ctx.time_step * ctx.time_step;
+            // I don't have any clue why 2nd order taylor expansion is **less** accurate than 1st order here
+            // maybe because the 1st order solver already provide a better approximation of the velocity
+            angular_velocity.0 += angular_part(&applied_momentum, i) * inertia.inv_inertia;
+            let angular_acceleration = angular_part(&applied_acceleration, i) * inertia.inv_inertia;
+            rot.actual += angular_velocity.0 * ctx.time_step
+                + real::<T>(0.5) * angular_acceleration * ctx.time_step * ctx.time_step;
+        }
+        Ok(())
+    }
+}
+
+/// this new attempt change when order are applied
+/// the "quality" of the solver can be measured by "how much the c constraint is far from 0"
+/// Baugmarte stabilization is the reason why there are energy losses, so this attempt try to minimize it usage (before implementation of soft constraint)
+/// first we make the velocity valid is this position, without applying any force
+/// then we apply forces at this location and update the velocity
+/// and we integrate the position
+///
+/// Taking a step back, wasn't bad, but was poorly set up
+/// In order to get the best accuracy, the new position need to validate constraints
+/// that mean the velocity need to be in the right "direction"
+pub struct HybridV2;
+
+impl<T: Real> Solver<T> for HybridV2 {
+    fn name(&self) -> &str {
+        "HybridV2"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        // both solvers work in a similar way, and can share a lot of calculations
+        // the most expensive is by far the factorisation of the K matrix, which is luckily common to both
+        // the J matrix only depends on the position, so it's common to both because position is updated at the end
+        let k = &ctx.j * &ctx.inv_mass * ctx.j.transpose();
+        // with unilateral constraints the system changes with the active set, so it can't be factorised once
+        let factorisation = if ctx.has_unilateral() { None } else { Some(ctx.factorise(&k)?) };
+        let bounds = ctx.bounds();
+        let mut stats = None;
+        let mut solve = |b: DVector<T>| match &factorisation {
+            Some(factorisation) => factorisation.solve(&b),
+            None => {
+                let (lambda, solve_stats) = projected_gauss_seidel(&k, &b, &bounds);
+                stats.get_or_insert_with(IterationStats::default).merge(solve_stats);
+                lambda
+            }
+        };
+        let inv_mass_matrix = &ctx.inv_mass;
+        let j = &ctx.j;
+        let jt = j.transpose();
+
+        // first, make velocity valid in this position
+        let q_dot = ctx.q_dot_vector();
+
+        let b = -j * q_dot - ctx.velocity_slack(); //no Baumgarte for now
+        let lambda = solve(b);
+        let applied_momentum = &jt * &lambda;
+
+        for (_, (velocity, mass, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+            .world
+            .query::<(&mut Velocity<T>, &Mass<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+            .iter()
+        {
+            velocity.0 += linear_part(&applied_momentum, i) * mass.inv_mass;
+            angular_velocity.0 += angular_part(&applied_momentum, i) * inertia.inv_inertia;
+        }
+        // now, we apply forces at this location and update the velocity
+        let force = &ctx.force;
+        let j_w_q2dot = j * inv_mass_matrix * force;
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        let b = -j_w_q2dot
+            - j_dot_q_dot
+            - ctx.force_slack();
+        let lambda = solve(b);
+        let applied_force = (jt * &lambda) + force;
+        if let Some(stats) = stats {
+            ctx.add_iteration_stats(stats);
+        }
+
+        //integrate velocity and position
+        for (_, (pos, velocity, mass, rot, angular_velocity, inertia, &SubjectToPhysic(i))) in ctx
+            .world
+            .query::<(&mut Position<T>, &mut Velocity<T>, &Mass<T>, &mut Rotation<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>()
+            .iter()
+        {
+            let acceleration = linear_part(&applied_force, i) * mass.inv_mass;
+            let temp = pos.actual;
+            let old_pos = pos.last_tick;
+            //Verlet integration
+            pos.actual = pos.actual + pos.actual - pos.last_tick
+                + acceleration * ctx.time_step * ctx.time_step;
+            pos.last_tick = temp;
+            velocity.0 = (pos.actual - old_pos) / (real::<T>(2.0) * ctx.time_step);
+
+            let angular_acceleration = angular_part(&applied_force, i) * inertia.inv_inertia;
+            let temp = rot.actual;
+            let old_rot = rot.last_tick;
+            rot.actual = rot.actual + rot.actual - rot.last_tick
+                + angular_acceleration * ctx.time_step * ctx.time_step;
+            rot.last_tick = temp;
+            angular_velocity.0 = (rot.actual - old_rot) / (real::<T>(2.0) * ctx.time_step);
+        }
+        Ok(())
+    }
+}
+
+/// I guessed most of this solver by playing around
+/// this is, by far, the most accurate solver, while being one of the fastest
+/// My original idea was to apply the second part of the second order to add a little extra information of acceleration
+/// turn out, I wasn't far from being successful, but I was missing a few things, where do this 1/2 applied on J_dot_q_dot comes from ?
+/// With more investigation, I found that a second order taylor expansion on "the position" gave this
+/// x(t + Dt) = x(t) + v(t) * Dt + 1/2 * a(t) * Dt^2
+/// (x(t + Dt) - x(t)) / Dt = v(t) + 1/2 * a(t) * Dt
+/// Some piece are still missing, but it looks like this solver is doing big part of the integration while working on the velocity
+/// which could explain it being so accurate
+/// My theory on the remaining energy loss, while being very low, is that the new velocity isn't really tangent to the movement
+pub struct HybridV3;
+
+impl<T: Real> Solver<T> for HybridV3 {
+    fn name(&self) -> &str {
+        "HybridV3"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let k = j * &ctx.inv_mass * &jt;
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+
+        let b = -j * q_dot - j_dot_q_dot * ctx.time_step * real::<T>(0.5) - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+
+        *ctx.applied_correction = &lambda / ctx.time_step; //since we are working with momentum, we need to divide by the time step to get the applied force
+
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+/// ``HybridV3`` with the system solved by a conjugate gradient, warm started with the multipliers of the last tick
+pub struct HybridV3cgm {
+    name: String,
+    conjugate_gradient: ConjugateGradient,
+}
+
+impl HybridV3cgm {
+    pub fn new(conjugate_gradient: ConjugateGradient) -> Self {
+        let name = match conjugate_gradient.preconditioner {
+            Preconditioner::None => "HybridV3cgm",
+            Preconditioner::Jacobi => "HybridV3cgmJacobi",
+            Preconditioner::IncompleteCholesky => "HybridV3cgmIc",
+        };
+        Self {
+            name: name.to_string(),
+            conjugate_gradient,
+        }
+    }
+}
+
+impl<T: Real> Solver<T> for HybridV3cgm {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let a = j * &ctx.inv_mass * &jt;
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        let b = -j * q_dot - j_dot_q_dot * ctx.time_step * real::<T>(0.5) - ctx.velocity_slack();
+
+        let guess = &*ctx.applied_correction * ctx.time_step; // it should be a nice guess, at least better than zeros
+        let lambda = ctx.solve_conjugate_gradient(&self.conjugate_gradient, a, &b, guess)?;
+
+        *ctx.applied_correction = &lambda / ctx.time_step; //since we are working with momentum, we need to divide by the time step to get the applied force
+
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+pub struct HybridV4;
+
+impl<T: Real> Solver<T> for HybridV4 {
+    fn name(&self) -> &str {
+        "HybridV4"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let k = j * &ctx.inv_mass * &jt;
+
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        let scary_thing = ctx.compute_ddot_q_dot_plus_j_dot_q_ddot();
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+        let b = -j * q_dot
+            - j_dot_q_dot * ctx.time_step * real::<T>(0.5)
+            - scary_thing * (real::<T>(1.0 / 6.0) * ctx.time_step * ctx.time_step)
+            - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        *ctx.applied_correction = &lambda / ctx.time_step;
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+pub struct Pbd;
+
+impl<T: Real> Solver<T> for Pbd {
+    fn name(&self) -> &str {
+        "Pbd"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        // first step, Verlet integration
+        for (_, (pos, rot, kind)) in ctx
+            .world
+            .query::<(&mut Position<T>, &mut Rotation<T>, &BodyKind<T>)>()
+            .iter()
+        {
+            let gravity = if kind.is_dynamic() { ctx.gravity } else { Vector2::zeros() };
+            // euler integration
+            let new_pos = pos.actual * real::<T>(2.0) - pos.last_tick + gravity * (ctx.time_step * ctx.time_step);
+            pos.last_tick = pos.actual;
+            pos.actual = new_pos;
+            let new_rot = real::<T>(2.0) * rot.actual - rot.last_tick;
+            rot.last_tick = rot.actual;
+            rot.actual = new_rot;
+        }
+        // only first order correction, bodies moved so the system must be assembled again
+        let j = ctx.j_matrix();
+        let jt = j.transpose();
+        let k = &j * &ctx.inv_mass * &jt;
+        let b = -ctx.c_vector();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        *ctx.applied_correction = &lambda / (ctx.time_step * ctx.time_step);
+        let applied_integrated_momentum = jt * &lambda;
+
+        for (_, (pos, vel, mass, rot, angular_vel, inertia, &SubjectToPhysic(i))) in ctx.world.query::<(&mut Position<T>, &mut Velocity<T>, &Mass<T>, &mut Rotation<T>, &mut AngularVelocity<T>, &Inertia<T>, &SubjectToPhysic)>().iter() {
+            pos.actual += linear_part(&applied_integrated_momentum, i) * mass.inv_mass;
+            vel.0 = (pos.last_tick - pos.actual) / ctx.time_step;
+            rot.actual += angular_part(&applied_integrated_momentum, i) * inertia.inv_inertia;
+            angular_vel.0 = (rot.last_tick - rot.actual) / ctx.time_step;
+        }
+        Ok(())
+    }
+}
+
+pub struct HybridV3Pbd;
+
+impl<T: Real> Solver<T> for HybridV3Pbd {
+    fn name(&self) -> &str {
+        "HybridV3Pbd"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let k = j * &ctx.inv_mass * &jt;
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        let b = -j * q_dot - j_dot_q_dot * ctx.time_step * real::<T>(0.5) - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+
+        *ctx.applied_correction = &lambda / ctx.time_step; //since we are working with momentum, we need to divide by the time step to get the applied force
+
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+
+        let j = ctx.j_matrix();
+        let jt = j.transpose();
+        let k = &j * &ctx.inv_mass * &jt;
+        let b = -ctx.c_vector();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        *ctx.applied_correction += &lambda / (ctx.time_step * ctx.time_step);
+        let applied_integrated_momentum = jt * &lambda;
+
+        for (_, (pos, mass, rot, inertia, &SubjectToPhysic(i))) in ctx.world.query::<(&mut Position<T>, &Mass<T>, &mut Rotation<T>, &Inertia<T>, &SubjectToPhysic)>().iter() {
+            pos.actual += linear_part(&applied_integrated_momentum, i) * mass.inv_mass;
+            rot.actual += angular_part(&applied_integrated_momentum, i) * inertia.inv_inertia;
+        }
+        Ok(())
+    }
+}
+
+pub struct FirstOrderSoft;
+
+impl<T: Real> Solver<T> for FirstOrderSoft {
+    fn name(&self) -> &str {
+        "FirstOrderSoft"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let m_eff = j * &ctx.inv_mass * &jt;
+
+        let gamma = ctx.gamma_matrix();
+
+        let k = m_eff + gamma * ctx.time_step.recip();
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+
+        let b = -j * q_dot - ctx.beta_c_over_h_vector() - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+        *ctx.applied_correction = &lambda / ctx.time_step;
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+pub struct HybridV3Soft;
+
+impl<T: Real> Solver<T> for HybridV3Soft {
+    fn name(&self) -> &str {
+        "HybridV3Soft"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let j = &ctx.j;
+        let jt = j.transpose();
+        let m_eff = j * &ctx.inv_mass * &jt;
+        let gamma = ctx.gamma_matrix();
+
+        let k = m_eff + gamma * ctx.time_step.recip();
+        let j_dot_q_dot = ctx.j_dot_q_dot();
+
+        ctx.apply_gravity(ctx.time_step);
+
+        // this velocity vector tends to violate the constraints
+        let q_dot = ctx.q_dot_vector();
+
+        let b = -j * q_dot
+            - j_dot_q_dot * ctx.time_step * real::<T>(0.5)
+            - ctx.beta_c_over_h_vector()
+            - ctx.velocity_slack();
+        let lambda = ctx.solve_lcp(k, &b)?;
+
+        *ctx.applied_correction = &lambda / ctx.time_step; //since we are working with momentum, we need to divide by the time step to get the applied force
+
+        let applied_momentum = jt * &lambda;
+
+        // correct the velocity
+        correct_velocity_and_integrate(ctx, &applied_momentum);
+        Ok(())
+    }
+}
+
+/// Box2D style sequential impulses: the constraints are corrected one at a time, each one updating the velocity
+/// before the next one is looked at, and this is repeated a fixed number of times.
+/// It never assembles nor factorises K.
+/// The velocity pass targets the same system as ``HybridV3Soft``, except for the drift of rigid constraints
+/// which is corrected by a second pass of split impulses: pseudo velocities that move the bodies
+/// but aren't kept in their velocity, so the correction doesn't inject energy.
+/// When ``warm_start`` is set, the multipliers of the previous tick are the starting point of the velocity pass.
+/// Contacts are rebuilt every tick, their previous multipliers are only a rough guess which the bounds keep in check
+pub struct SequentialImpulse {
+    name: String,
+    iterations: usize,
+    warm_start: bool,
+}
+
+impl SequentialImpulse {
+    pub fn new(iterations: usize, warm_start: bool) -> Self {
+        let name = if warm_start {
+            format!("SequentialImpulse{iterations}")
+        } else {
+            format!("SequentialImpulse{iterations}Cold")
+        };
+        Self {
+            name,
+            iterations,
+            warm_start,
+        }
+    }
+}
+
+/// what the Gauss-Seidel solvers need to know about every row
+struct SequentialRows<'a, T: Real> {
+    /// the columns of J transposed are the rows of J
+    jt: CscMatrix<T>,
+    /// M^-1 is diagonal, it has exactly one value per column
+    inv_mass: &'a [T],
+    /// gamma / h, 0 for rigid constraints
+    softness: Vec<T>,
+    /// J_i * M^-1 * J_i^T + gamma_i / h
+    effective_mass: Vec<T>,
+    constraints: &'a [&'a Constraint<T>],
+}
+
+impl<'a, T: Real> SequentialRows<'a, T> {
+    fn new(j: &CscMatrix<T>, inv_mass: &'a CscMatrix<T>, constraints: &'a [&'a Constraint<T>], time_step: T) -> Self {
+        let jt = j.transpose();
+        let inv_mass = inv_mass.values();
+        let softness: Vec<T> = constraints
+            .iter()
+            .map(|c| (c.damping + time_step * c.stiffness).recip() / time_step)
+            .collect();
+        let effective_mass = (0..jt.ncols())
+            .map(|i| {
+                let row = jt.col(i);
+                row.row_indices()
+                    .iter()
+                    .zip(row.values())
+                    .fold(softness[i], |sum, (&column, &value)| sum + value * value * inv_mass[column])
+            })
+            .collect();
+        Self {
+            jt,
+            inv_mass,
+            softness,
+            effective_mass,
+            constraints,
+        }
+    }
+
+    /// one Gauss-Seidel sweep over the rows, ``lambda`` is accumulated
+    /// and its effect is applied to ``q_dot`` right away. Rows with an infinite bias are left untouched
+    fn sweep(&self, bias: &DVector<T>, q_dot: &mut DVector<T>, lambda: &mut DVector<T>) {
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            if !bias[i].is_finite() || self.effective_mass[i] <= T::zero() {
+                continue;
+            }
+            let row = self.jt.col(i);
+            let j_q_dot = row
+                .row_indices()
+                .iter()
+                .zip(row.values())
+                .fold(T::zero(), |sum, (&column, &value)| sum + value * q_dot[column]);
+            let residual = -(j_q_dot + bias[i] + self.softness[i] * lambda[i]);
+            let updated = constraint
+                .bound
+                .clamp(lambda[i] + constraint.relaxation * residual / self.effective_mass[i]);
+            let delta = updated - lambda[i];
+            lambda[i] = updated;
+            for (&column, &value) in row.row_indices().iter().zip(row.values()) {
+                q_dot[column] += self.inv_mass[column] * value * delta;
+            }
+        }
+    }
+}
+
+/// velocity bias of the rows for a step of ``time_step``, split in two parts:
+/// the springs of the soft constraints and the slack of the inactive unilateral ones,
+/// and the drift correction of the active rigid constraints.
+/// Rows without any drift to correct are infinite in the second part, so a sweep leaves them untouched
+fn spring_and_drift<T: Real>(constraints: &[&Constraint<T>], c: &DVector<T>, time_step: T) -> (DVector<T>, DVector<T>) {
+    let (spring, drift): (Vec<T>, Vec<T>) = constraints
+        .iter()
+        .zip(c.iter())
+        .map(|(constraint, &c)| {
+            if constraint.bound.is_inactive(c) {
+                (c / time_step, real(f64::INFINITY))
+            } else if constraint.stiffness.is_finite() {
+                let beta = (time_step * constraint.stiffness)
+                    / (constraint.damping + time_step * constraint.stiffness);
+                (c * beta / time_step, real(f64::INFINITY))
+            } else {
+                (T::zero(), c / time_step)
+            }
+        })
+        .unzip();
+    (DVector::from_vec(spring), DVector::from_vec(drift))
+}
+
+impl<T: Real> Solver<T> for SequentialImpulse {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let rows = SequentialRows::new(&ctx.j, &ctx.inv_mass, &ctx.constraints, ctx.time_step);
+        let len = rows.constraints.len();
+
+        // springs stay in the velocity pass, the drift of rigid constraints is left to the position pass
+        let (spring, position_bias) = spring_and_drift(rows.constraints, &ctx.c, ctx.time_step);
+        let velocity_bias = ctx.j_dot_q_dot() * (ctx.time_step * real::<T>(0.5)) + spring;
+
+        ctx.apply_gravity(ctx.time_step);
+
+        let mut lambda = if self.warm_start && ctx.applied_correction.len() == len {
+            let mut lambda = &*ctx.applied_correction * ctx.time_step;
+            lambda
+                .iter_mut()
+                .zip(rows.constraints)
+                .for_each(|(l, c)| *l = c.bound.clamp(*l));
+            lambda
+        } else {
+            DVector::zeros(len)
+        };
+        let mut q_dot = ctx.q_dot_vector() + &ctx.inv_mass * (&rows.jt * &lambda);
+        for _ in 0..self.iterations {
+            rows.sweep(&velocity_bias, &mut q_dot, &mut lambda);
+        }
+
+        let mut pseudo_q_dot = DVector::zeros(q_dot.len());
+        let mut pseudo_lambda = DVector::zeros(len);
+        for _ in 0..self.iterations {
+            rows.sweep(&position_bias, &mut pseudo_q_dot, &mut pseudo_lambda);
+        }
+
+        *ctx.applied_correction = &lambda / ctx.time_step;
+        write_velocity(ctx.world, &q_dot);
+        integrate_position(ctx.world, &(&q_dot + pseudo_q_dot), ctx.time_step);
+        Ok(())
+    }
+}
+
+/// write ``q_dot`` as the velocity of the bodies
+fn write_velocity<T: Real>(world: &World, q_dot: &DVector<T>) {
+    for (_, (velocity, angular_velocity, &SubjectToPhysic(i))) in world
+        .query::<(&mut Velocity<T>, &mut AngularVelocity<T>, &SubjectToPhysic)>()
+        .iter()
+    {
+        velocity.0 = linear_part(q_dot, i);
+        angular_velocity.0 = angular_part(q_dot, i);
+    }
+}
+
+/// move the bodies by ``q_dot * time_step``
+fn integrate_position<T: Real>(world: &World, q_dot: &DVector<T>, time_step: T) {
+    for (_, (pos, rot, &SubjectToPhysic(i))) in world
+        .query::<(&mut Position<T>, &mut Rotation<T>, &SubjectToPhysic)>()
+        .iter()
+    {
+        pos.actual += linear_part(q_dot, i) * time_step;
+        rot.actual += angular_part(q_dot, i) * time_step;
+    }
+}
+
+/// Temporal Gauss-Seidel, the sub-stepping scheme of Box2d v3 and Rapier: the tick is split into
+/// ``SolverContext::substeps`` substeps, each one doing a single Gauss-Seidel iteration.
+/// The jacobian and the constraints are evaluated again at the beginning of every substep,
+/// the linearisation error of a short substep is small, that's where the accuracy comes from.
+/// A substep solves with the drift correction, moves the bodies, then relaxes the velocity without it.
+/// The multipliers of a substep are the starting point of the next one
+pub struct Tgs;
+
+impl<T: Real> Solver<T> for Tgs {
+    fn name(&self) -> &str {
+        "Tgs"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let substeps = ctx.substeps.max(1);
+        let time_step = ctx.time_step / real(substeps as f64);
+        let len = ctx.constraints.len();
+        let mut lambda = DVector::zeros(len);
+        let mut impulse = DVector::zeros(len);
+
+        for substep in 0..substeps {
+            if substep > 0 {
+                ctx.j = ctx.j_matrix();
+                ctx.c = ctx.c_vector();
+            }
+            ctx.apply_gravity(time_step);
+
+            let rows = SequentialRows::new(&ctx.j, &ctx.inv_mass, &ctx.constraints, time_step);
+            let (spring, drift) = spring_and_drift(rows.constraints, &ctx.c, time_step);
+            let bias = spring.zip_map(&drift, |spring, drift| {
+                if drift.is_finite() { spring + drift } else { spring }
+            });
+
+            let mut q_dot = ctx.q_dot_vector() + &ctx.inv_mass * (&rows.jt * &lambda);
+            rows.sweep(&bias, &mut q_dot, &mut lambda);
+            integrate_position(ctx.world, &q_dot, time_step);
+            rows.sweep(&spring, &mut q_dot, &mut lambda);
+            write_velocity(ctx.world, &q_dot);
+            impulse += &lambda;
+        }
+
+        *ctx.applied_correction = impulse / ctx.time_step;
+        Ok(())
+    }
+}
+
+/// Extended position based dynamics (Macklin, Müller and Chentanez 2016).
+/// Unlike ``Pbd``, the constraints are projected one at a time for several iterations,
+/// C and its gradient are evaluated again after every projection, so the projection is non linear.
+/// Soft constraints are honoured through their compliance (1 / stiffness) and damping,
+/// so the soft scenes behave like they do with the soft velocity level solvers
+pub struct Xpbd {
+    name: String,
+    iterations: usize,
+}
+
+impl Xpbd {
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            name: format!("Xpbd{iterations}"),
+            iterations,
+        }
+    }
+}
+
+impl<T: Real> Solver<T> for Xpbd {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let h = ctx.time_step;
+        // predict the positions, the last tick is kept to derive the velocity afterward
+        for (_, (pos, velocity, rot, angular_velocity, kind)) in ctx
+            .world
+            .query::<(&mut Position<T>, &mut Velocity<T>, &mut Rotation<T>, &AngularVelocity<T>, &BodyKind<T>)>()
+            .iter()
+        {
+            if kind.is_dynamic() {
+                velocity.0 += ctx.gravity * h;
+            }
+            pos.last_tick = pos.actual;
+            pos.actual += velocity.0 * h;
+            rot.last_tick = rot.actual;
+            rot.actual += angular_velocity.0 * h;
+        }
+
+        let inv_mass = ctx.inv_mass.values();
+        let mut lambda = DVector::<T>::zeros(ctx.constraints.len());
+        let mut row = JacobianRow::default();
+        for _ in 0..self.iterations {
+            for (i, constraint) in ctx.constraints.iter().enumerate() {
+                // compliance and damping scaled by the time step, alpha~ and gamma in the paper
+                let alpha = (constraint.stiffness * h * h).recip();
+                let gamma = constraint.damping / (constraint.stiffness * h);
+                let (c, w, damping) = {
+                    let mut query = ctx.world.query::<(&Position<T>, &Rotation<T>, &SubjectToPhysic)>();
+                    let view = query.view();
+                    row.clear();
+                    constraint.expression.build_j_row(&view, &mut row);
+                    let c = constraint
+                        .expression
+                        .evaluate_c(&ctx.world.query::<(&Position<T>, &Rotation<T>)>().view());
+                    let mut w = T::zero();
+                    let mut damping = T::zero();
+                    for &(column, value) in row.entries() {
+                        let (pos, rot, _) = view.get(ctx.physic_index_to_entity[column / DOF]).unwrap();
+                        let displacement = match column % DOF {
+                            0 => pos.actual.x - pos.last_tick.x,
+                            1 => pos.actual.y - pos.last_tick.y,
+                            _ => rot.actual - rot.last_tick,
+                        };
+                        w += value * value * inv_mass[column];
+                        damping += value * displacement;
+                    }
+                    (c, w, damping)
+                };
+                let denominator = (T::one() + gamma) * w + alpha;
+                if denominator <= T::zero() {
+                    continue;
+                }
+                let delta = (-c - alpha * lambda[i] - gamma * damping) / denominator;
+                let updated = constraint.bound.clamp(lambda[i] + delta);
+                let delta = updated - lambda[i];
+                lambda[i] = updated;
+
+                for &(column, value) in row.entries() {
+                    let entity = ctx.physic_index_to_entity[column / DOF];
+                    let correction = inv_mass[column] * value * delta;
+                    match column % DOF {
+                        0 => ctx.world.get::<&mut Position<T>>(entity).unwrap().actual.x += correction,
+                        1 => ctx.world.get::<&mut Position<T>>(entity).unwrap().actual.y += correction,
+                        _ => ctx.world.get::<&mut Rotation<T>>(entity).unwrap().actual += correction,
+                    }
+                }
+            }
+        }
+        *ctx.applied_correction = &lambda / (h * h);
+
+        for (_, (pos, velocity, rot, angular_velocity)) in ctx
+            .world
+            .query::<(&Position<T>, &mut Velocity<T>, &Rotation<T>, &mut AngularVelocity<T>)>()
+            .iter()
+        {
+            velocity.0 = (pos.actual - pos.last_tick) / h;
+            angular_velocity.0 = (rot.actual - rot.last_tick) / h;
+        }
+        Ok(())
+    }
+}
+
+/// Fully implicit (backward) Euler. The velocity at the end of the step v solves
+/// M * (v - v0) = h * (F + spring forces at x0 + h * v) + J^T * lambda,
+/// with every rigid constraint holding at x0 + h * v.
+/// The non linear equations are solved with Newton iterations, the spring forces are linearised with the jacobian of
+/// their constraint (the curvature of C is left out), and every step is shortened by a backtracking line search
+/// until the residual decreases.
+/// The stiffness isn't folded into a velocity level bias like with ``gamma_matrix``,
+/// very stiff springs stay stable, at the price of some numerical damping
+pub struct ImplicitEuler {
+    name: String,
+    iterations: usize,
+}
+
+impl ImplicitEuler {
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            name: format!("ImplicitEuler{iterations}"),
+            iterations,
+        }
+    }
+}
+
+/// everything an ``ImplicitEuler`` step needs that doesn't change between Newton iterations
+struct ImplicitStep<T: Real> {
+    time_step: T,
+    /// diagonal of M, 0 for the degrees of freedom that can't move (point masses don't rotate)
+    mass: DVector<T>,
+    /// M^-1/2, it weights the residual of the dynamics in the merit function
+    sqrt_inv_mass: CscMatrix<T>,
+    force: DVector<T>,
+    q0: DVector<T>,
+    q_dot0: DVector<T>,
+    /// stiffness and damping of every row, 0 for the rigid ones
+    stiffness: DVector<T>,
+    damping: DVector<T>,
+    /// rows of the rigid constraints, they are enforced instead of producing a spring force
+    rigid: Vec<usize>,
+    bounds: Vec<Bound>,
+}
+
+impl<T: Real> ImplicitStep<T> {
+    /// move the bodies to the end of the step for the velocity ``q_dot``, and evaluate J and C there
+    fn evaluate(&self, ctx: &mut SolverContext<T>, q_dot: &DVector<T>) -> (CscMatrix<T>, DVector<T>) {
+        ctx.set_q_vector(&(&self.q0 + q_dot * self.time_step));
+        (ctx.j_matrix(), ctx.c_vector())
+    }
+
+    /// M * (v - v0) - h * (F + spring forces), the rigid constraints are left out
+    fn spring_residual(&self, j: &CscMatrix<T>, c: &DVector<T>, q_dot: &DVector<T>) -> DVector<T> {
+        let spring = self.stiffness.component_mul(c) + self.damping.component_mul(&(j * q_dot));
+        self.mass.component_mul(&(q_dot - &self.q_dot0)) - &self.force * self.time_step
+            + j.transpose() * (spring * self.time_step)
+    }
+
+    /// what the line search tries to reduce: the residual of the dynamics and the violation of the rigid constraints
+    fn merit(&self, j: &CscMatrix<T>, c: &DVector<T>, q_dot: &DVector<T>, lambda: &DVector<T>) -> T {
+        let residual = self.spring_residual(j, c, q_dot) - select_rows(j, &self.rigid).transpose() * lambda;
+        let violation = self
+            .rigid
+            .iter()
+            .zip(&self.bounds)
+            .fold(T::zero(), |sum, (&i, bound)| sum + (bound.violation(c[i]) / self.time_step).powi(2));
+        (&self.sqrt_inv_mass * residual).norm_squared() + violation
+    }
+}
+
+/// rows of the rigid constraints, the ones with an infinite stiffness
+fn rigid_rows<T: Real>(constraints: &[&Constraint<T>]) -> Vec<usize> {
+    (0..constraints.len())
+        .filter(|&i| !constraints[i].stiffness.is_finite())
+        .collect()
+}
+
+/// ``value`` of every soft constraint, 0 for the rigid ones
+fn soft_coefficients<T: Real>(constraints: &[&Constraint<T>], value: fn(&Constraint<T>) -> T) -> DVector<T> {
+    DVector::from_iterator(
+        constraints.len(),
+        constraints
+            .iter()
+            .map(|c| if c.stiffness.is_finite() { value(c) } else { T::zero() }),
+    )
+}
+
+/// sub matrix made of the given rows
+fn select_rows<T: Real>(matrix: &CscMatrix<T>, rows: &[usize]) -> CscMatrix<T> {
+    let transposed = matrix.transpose();
+    let mut selected = CooMatrix::new(rows.len(), matrix.ncols());
+    for (i, &row) in rows.iter().enumerate() {
+        let entries = transposed.col(row);
+        for (&column, &value) in entries.row_indices().iter().zip(entries.values()) {
+            selected.push(i, column, value);
+        }
+    }
+    CscMatrix::from(&selected)
+}
+
+impl<T: Real> Solver<T> for ImplicitEuler {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let h = ctx.time_step;
+        let inv_mass = ctx.inv_mass.values();
+        let rigid = rigid_rows(&ctx.constraints);
+        let step = ImplicitStep {
+            time_step: h,
+            mass: DVector::from_iterator(
+                inv_mass.len(),
+                inv_mass
+                    .iter()
+                    .map(|&w| if w > T::zero() { w.recip() } else { T::zero() }),
+            ),
+            sqrt_inv_mass: diagonal_matrix(inv_mass.iter().map(|w| w.sqrt())),
+            force: ctx.force.clone(),
+            q0: ctx.q_vector(),
+            q_dot0: ctx.q_dot_vector(),
+            stiffness: soft_coefficients(&ctx.constraints, |c| c.stiffness),
+            damping: soft_coefficients(&ctx.constraints, |c| c.damping),
+            bounds: rigid.iter().map(|&i| ctx.constraints[i].bound).collect(),
+            rigid,
+        };
+        // the spring forces are linearised as h * (h * k + d) * J * dv, a soft row behaves like a rigid one with a
+        // compliance 1 / (h^2 * k + h * d) and the whole Newton system stays J * M^-1 * J^T + compliance, as sparse
+        // as J. The soft rows without stiffness nor damping apply no force and are left out
+        let rows: Vec<usize> = (0..ctx.constraints.len())
+            .filter(|&i| {
+                step.rigid.binary_search(&i).is_ok() || step.stiffness[i] > T::zero() || step.damping[i] > T::zero()
+            })
+            .collect();
+        let rigid_in_rows: Vec<usize> = step.rigid.iter().map(|i| rows.binary_search(i).unwrap()).collect();
+        let compliance = diagonal_matrix(rows.iter().map(|&i| {
+            if step.rigid.binary_search(&i).is_ok() {
+                T::zero()
+            } else {
+                (h * h * step.stiffness[i] + h * step.damping[i]).recip()
+            }
+        }));
+        let bounds: Vec<Bound> = rows
+            .iter()
+            .map(|&i| {
+                let bound = ctx.constraints[i].bound;
+                if step.rigid.binary_search(&i).is_ok() { bound } else { Bound::Equality }
+            })
+            .collect();
+        let equality_only = bounds.iter().all(|&bound| bound == Bound::Equality);
+
+        // an explicit step is the first guess
+        let mut q_dot = &step.q_dot0 + &ctx.inv_mass * &step.force * h;
+        let (mut j, mut c) = step.evaluate(ctx, &q_dot);
+        let mut lambda = DVector::zeros(step.rigid.len());
+        let mut converged = false;
+        let mut iterations = 0;
+        let mut last_update = T::zero();
+        while iterations < self.iterations && !converged {
+            iterations += 1;
+            let residual = step.spring_residual(&j, &c, &q_dot);
+
+            // M * dv = -residual + J^T * mu, with J_r * dv = -C_r / h for the rigid rows
+            // and J_s * dv + compliance * mu = 0 for the soft ones
+            let j_rows = select_rows(&j, &rows);
+            let inv_mass_j_t = &ctx.inv_mass * j_rows.transpose();
+            let k = &j_rows * &inv_mass_j_t + &compliance;
+            let mut b = &j_rows * (&ctx.inv_mass * &residual);
+            for (&row, &i) in rigid_in_rows.iter().zip(&step.rigid) {
+                b[row] -= c[i] / h;
+            }
+            let mu = if equality_only {
+                let factorisation = Factorisation::new(&k)?;
+                ctx.redundant_constraints = factorisation.redundant().iter().map(|&k| rows[k]).collect();
+                factorisation.solve(&b)
+            } else {
+                let (mu, stats) = projected_gauss_seidel(&k, &b, &bounds);
+                ctx.add_iteration_stats(stats);
+                mu
+            };
+            lambda = DVector::from_iterator(step.rigid.len(), rigid_in_rows.iter().map(|&k| mu[k]));
+            let delta = &ctx.inv_mass * (-residual) + inv_mass_j_t * mu;
+
+            let current = step.merit(&j, &c, &q_dot, &lambda);
+            let mut alpha = T::one();
+            let mut trial = &q_dot + &delta;
+            (j, c) = step.evaluate(ctx, &trial);
+            for _ in 0..LINE_SEARCH_STEPS {
+                if step.merit(&j, &c, &trial, &lambda) < current {
+                    break;
+                }
+                alpha *= real(0.5);
+                trial = &q_dot + &delta * alpha;
+                (j, c) = step.evaluate(ctx, &trial);
+            }
+            q_dot = trial;
+            last_update = delta.amax() * alpha;
+            converged = last_update < real(NEWTON_TOLERANCE);
+        }
+        // stopping at the cap leaves a step that doesn't quite solve the implicit equations, it is kept anyway
+        ctx.add_iteration_stats(IterationStats::single(iterations, last_update, converged));
+
+        // the bodies are already at the end of the step
+        write_velocity(ctx.world, &q_dot);
+        let spring = -(step.stiffness.component_mul(&c) + step.damping.component_mul(&(&j * &q_dot)));
+        *ctx.applied_correction = spring;
+        for (&i, &lambda) in step.rigid.iter().zip(lambda.iter()) {
+            ctx.applied_correction[i] = lambda / h;
+        }
+        Ok(())
+    }
+}
+
+/// RATTLE, the constrained velocity Verlet. It is symplectic and time reversible, the energy doesn't drift,
+/// it oscillates around the right value.
+/// With lambda and mu the forces of the rigid constraints:
+/// - v_half = v + h / 2 * M^-1 * (F(x) + J(x)^T * lambda), x' = x + h * v_half, with lambda such that C(x') = 0
+/// - v' = v_half + h / 2 * M^-1 * (F(x') + J(x')^T * mu), with mu such that J(x') * v' = 0
+///
+/// The first equation is non linear (SHAKE), it is solved by iterating with the matrix J(x) * M^-1 * J(x)^T
+/// factorised once. The soft constraints are projected with the rigid ones, with a compliance 1 / stiffness like
+/// in ``Xpbd``: their force is the spring force at x', applied in both half kicks. An explicit spring force would
+/// leave the stable range of the step with stiff springs, this stays stable but damps them a little,
+/// only the rigid part of the system is symplectic.
+/// The unilateral constraints only push or pull, this breaks the time reversibility of a contact, nothing else
+pub struct Rattle {
+    name: String,
+    iterations: usize,
+}
+
+impl Rattle {
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            name: format!("Rattle{iterations}"),
+            iterations,
+        }
+    }
+}
+
+impl<T: Real> Solver<T> for Rattle {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<T>) -> Result<(), SolveError> {
+        let h = ctx.time_step;
+        let half = h * real(0.5);
+        let scale = half * h;
+        let bounds = ctx.bounds();
+        let equality_only = bounds.iter().all(|&bound| bound == Bound::Equality);
+        // every row holds C + lambda / stiffness + damping / (stiffness * h) * J * (x' - x) = 0 at the end of the
+        // position stage, the rigid rows have both coefficients at 0
+        let compliance = soft_coefficients(&ctx.constraints, |c| c.stiffness.recip());
+        let damping_ratio = soft_coefficients(&ctx.constraints, |c| c.damping / c.stiffness) / h;
+        let rigid = rigid_rows(&ctx.constraints);
+
+        let q0 = ctx.q_vector();
+        let q_dot0 = ctx.q_dot_vector();
+
+        // position stage, x' = predicted + h^2 / 2 * M^-1 * J(x)^T * lambda
+        let j0 = ctx.j.clone();
+        let inv_mass_j_t = &ctx.inv_mass * j0.transpose();
+        // the rows are divided by 1 + damping ratio to keep the system symmetric
+        let k = &j0 * &inv_mass_j_t
+            + diagonal_matrix(
+                compliance
+                    .iter()
+                    .zip(damping_ratio.iter())
+                    .map(|(&compliance, &ratio)| compliance / (scale * (T::one() + ratio))),
+            );
+        let factorisation = if equality_only { Some(ctx.factorise(&k)?) } else { None };
+        let mut stats = None;
+        let mut solve = |k: &CscMatrix<T>, b: &DVector<T>, factorisation: &Option<Factorisation<T>>, bounds: &[Bound]| {
+            match factorisation {
+                Some(factorisation) => factorisation.solve(b),
+                None => {
+                    let (lambda, solve_stats) = projected_gauss_seidel(k, b, bounds);
+                    stats.get_or_insert_with(IterationStats::default).merge(solve_stats);
+                    lambda
+                }
+            }
+        };
+        let predicted = &q0 + (&q_dot0 + &ctx.inv_mass * &ctx.force * half) * h;
+        let mut lambda = DVector::zeros(ctx.constraints.len());
+        let mut shake = IterationStats::default();
+        for iteration in 0.. {
+            let q = &predicted + &inv_mass_j_t * &lambda * scale;
+            ctx.set_q_vector(&q);
+            let residual = ctx.c_vector()
+                + compliance.component_mul(&lambda)
+                + damping_ratio.component_mul(&(&j0 * (q - &q0)));
+            let violation = residual
+                .iter()
+                .zip(&bounds)
+                .fold(T::zero(), |max, (&r, bound)| max.max(bound.violation(r).abs()));
+            let converged = violation < real(SHAKE_TOLERANCE);
+            if converged || iteration == self.iterations {
+                // at the cap the constraints are still violated by more than the tolerance, the positions are kept
+                shake = IterationStats::single(iteration, violation, converged);
+                break;
+            }
+            // the step is a Newton step with J(x') replaced by J(x), the multipliers are solved as a whole to keep
+            // the bounds on the total force
+            let b = &k * &lambda
+                - residual.zip_map(&damping_ratio, |residual, ratio| residual / (scale * (T::one() + ratio)));
+            lambda = solve(&k, &b, &factorisation, &bounds);
+        }
+        let q_dot_half = &q_dot0 + &ctx.inv_mass * (&ctx.force + j0.transpose() * &lambda) * half;
+
+        // velocity stage, at the end of the step the springs apply the force found by the position stage
+        let j = ctx.j_matrix();
+        let c = ctx.c_vector();
+        let spring = lambda.zip_map(&compliance, |lambda, compliance| {
+            if compliance > T::zero() { lambda } else { T::zero() }
+        });
+        let q_dot_free = &q_dot_half + &ctx.inv_mass * (&ctx.force + j.transpose() * &spring) * half;
+        let j_r = select_rows(&j, &rigid);
+        let j_r_t = j_r.transpose();
+        let k = &j_r * &ctx.inv_mass * &j_r_t;
+        let rigid_bounds: Vec<Bound> = rigid.iter().map(|&i| bounds[i]).collect();
+        // a constraint satisfied with some margin, a slack rope, has no velocity to correct
+        let mut b = -(&j_r * &q_dot_free) / half;
+        for ((b, &i), bound) in b.iter_mut().zip(&rigid).zip(&rigid_bounds) {
+            if bound.is_inactive(c[i]) {
+                *b = real(f64::INFINITY);
+            }
+        }
+        let factorisation = equality_only.then(|| Factorisation::new(&k)).transpose()?;
+        let mu = solve(&k, &b, &factorisation, &rigid_bounds);
+        if let Some(stats) = stats {
+            ctx.add_iteration_stats(stats);
+        }
+        ctx.add_iteration_stats(shake);
+        let q_dot = q_dot_free + &ctx.inv_mass * &j_r_t * &mu * half;
+        write_velocity(ctx.world, &q_dot);
+
+        *ctx.applied_correction = spring;
+        for (k, &i) in rigid.iter().enumerate() {
+            ctx.applied_correction[i] = (lambda[i] + mu[k]) * real(0.5);
+        }
+        Ok(())
+    }
+}
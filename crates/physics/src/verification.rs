@@ -0,0 +1,245 @@
+//! Check the derivatives of a ``ConstraintExpression`` against central finite differences of ``evaluate_c``.
+//! The constraint is built on the bodies of a ``DerivativeBench``, which reports the worst error of each method.
+//! The bodies are given random states and every method is compared to what ``evaluate_c`` says it should be,
+//! a hand written derivative that disagrees with C shows up as a large error.
+//! Finite differences need the precision, the checks are done in f64.
+
+use crate::components::{
+    Acceleration, AngularVelocity, Position, Rotation, SubjectToPhysic, Velocity,
+};
+use crate::constraints::{AccelerationQuery, ConstraintExpression, DOF, JacobianRow, VelocityQuery};
+use hecs::{Entity, World};
+use nalgebra::Vector2;
+use std::fmt;
+
+/// step of the first order differences
+const STEP: f64 = 1e-6;
+/// step of the second order differences, a smaller one loses everything to rounding
+const SECOND_ORDER_STEP: f64 = 1e-4;
+
+/// Worst relative error of each method over every sampled state, see ``DerivativeBench::check``.
+/// The error is relative to the finite difference, it becomes absolute for differences smaller than 1
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DerivativeErrors {
+    /// ``build_j_row`` against the partial derivatives of C
+    pub j_row: f64,
+    /// ``evaluate_c_dot`` against the derivative of C along the velocity
+    pub c_dot: f64,
+    /// ``compute_j_dot_q_dot`` against the second derivative of C along the velocity
+    pub j_dot_q_dot: f64,
+    /// ``compute_ddot_q_dot_plus_j_dot_q_ddot`` against the time derivative of ``compute_j_dot_q_dot``
+    /// when the bodies move with their velocity and linear acceleration
+    pub third_order: f64,
+}
+
+impl DerivativeErrors {
+    /// the largest error of every method
+    pub fn max(&self) -> f64 {
+        self.j_row.max(self.c_dot).max(self.j_dot_q_dot).max(self.third_order)
+    }
+}
+
+impl fmt::Display for DerivativeErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "j_row {:.1e}  c_dot {:.1e}  j_dot_q_dot {:.1e}  third_order {:.1e}",
+            self.j_row, self.c_dot, self.j_dot_q_dot, self.third_order
+        )
+    }
+}
+
+/// xorshift, the checks must be reproducible and don't need a good generator
+struct Random(u64);
+
+impl Random {
+    /// uniform in [-range, range]
+    fn next(&mut self, range: f64) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0) * range
+    }
+
+    fn vector(&mut self, range: f64) -> Vector2<f64> {
+        Vector2::new(self.next(range), self.next(range))
+    }
+}
+
+/// state of one body, it is written to the world before every evaluation
+#[derive(Debug, Clone, Copy)]
+struct BodyState {
+    position: Vector2<f64>,
+    angle: f64,
+    velocity: Vector2<f64>,
+    angular_velocity: f64,
+    acceleration: Vector2<f64>,
+}
+
+impl BodyState {
+    /// the state after ``time`` when the body keeps its acceleration
+    fn advanced(&self, time: f64) -> Self {
+        Self {
+            position: self.position + self.velocity * time + self.acceleration * (time * time / 2.0),
+            angle: self.angle + self.angular_velocity * time,
+            velocity: self.velocity + self.acceleration * time,
+            ..*self
+        }
+    }
+
+    /// the coordinates moved by ``step`` along the velocity
+    fn moved(&self, step: f64) -> Self {
+        Self {
+            position: self.position + self.velocity * step,
+            angle: self.angle + self.angular_velocity * step,
+            ..*self
+        }
+    }
+}
+
+/// Bodies to build a constraint on before checking it with ``check``.
+/// Every body has a position, a rotation, a velocity and an acceleration that ``check`` overwrites
+pub struct DerivativeBench {
+    world: World,
+    bodies: Vec<Entity>,
+}
+
+impl DerivativeBench {
+    /// spawn ``bodies`` bodies, their ``SubjectToPhysic`` index is their place in ``bodies()``
+    pub fn new(bodies: usize) -> Self {
+        let mut world = World::new();
+        let bodies = (0..bodies)
+            .map(|i| {
+                world.spawn((
+                    Position {
+                        actual: Vector2::<f64>::zeros(),
+                        last_tick: Vector2::zeros(),
+                    },
+                    Rotation {
+                        actual: 0.0,
+                        last_tick: 0.0,
+                    },
+                    Velocity(Vector2::<f64>::zeros()),
+                    AngularVelocity(0.0),
+                    Acceleration(Vector2::<f64>::zeros()),
+                    SubjectToPhysic(i),
+                ))
+            })
+            .collect();
+        Self { world, bodies }
+    }
+
+    /// the entities to build the constraint on
+    pub fn bodies(&self) -> &[Entity] {
+        &self.bodies
+    }
+
+    fn set(&mut self, states: &[BodyState]) {
+        for (&e, state) in self.bodies.iter().zip(states) {
+            let mut position = self.world.get::<&mut Position<f64>>(e).unwrap();
+            position.actual = state.position;
+            position.last_tick = state.position;
+            let mut rotation = self.world.get::<&mut Rotation<f64>>(e).unwrap();
+            rotation.actual = state.angle;
+            rotation.last_tick = state.angle;
+            self.world.get::<&mut Velocity<f64>>(e).unwrap().0 = state.velocity;
+            self.world.get::<&mut AngularVelocity<f64>>(e).unwrap().0 = state.angular_velocity;
+            self.world.get::<&mut Acceleration<f64>>(e).unwrap().0 = state.acceleration;
+        }
+    }
+
+    fn c(&mut self, expression: &dyn ConstraintExpression<f64>, states: &[BodyState]) -> f64 {
+        self.set(states);
+        expression.evaluate_c(&self.world.query::<(&Position<f64>, &Rotation<f64>)>().view())
+    }
+
+    fn j_row(&mut self, expression: &dyn ConstraintExpression<f64>, states: &[BodyState]) -> JacobianRow<f64> {
+        self.set(states);
+        let mut row = JacobianRow::default();
+        expression.build_j_row(
+            &self.world.query::<(&Position<f64>, &Rotation<f64>, &SubjectToPhysic)>().view(),
+            &mut row,
+        );
+        row
+    }
+
+    fn c_dot(&mut self, expression: &dyn ConstraintExpression<f64>, states: &[BodyState]) -> f64 {
+        self.set(states);
+        expression.evaluate_c_dot(&self.world.query::<VelocityQuery<f64>>().view())
+    }
+
+    fn j_dot_q_dot(&mut self, expression: &dyn ConstraintExpression<f64>, states: &[BodyState]) -> f64 {
+        self.set(states);
+        expression.compute_j_dot_q_dot(&self.world.query::<VelocityQuery<f64>>().view())
+    }
+
+    fn third_order(&mut self, expression: &dyn ConstraintExpression<f64>, states: &[BodyState]) -> f64 {
+        self.set(states);
+        expression.compute_ddot_q_dot_plus_j_dot_q_ddot(&self.world.query::<AccelerationQuery<f64>>().view())
+    }
+
+    /// Compare the derivatives of ``expression``, built on ``bodies()``, with finite differences of ``evaluate_c``
+    /// for ``samples`` random states drawn from ``seed``.
+    /// Positions are drawn in [-2, 2], angles in [-pi, pi], velocities and accelerations in [-1, 1].
+    /// The angular acceleration isn't a component, it is always 0
+    pub fn check(&mut self, expression: &dyn ConstraintExpression<f64>, samples: usize, seed: u64) -> DerivativeErrors {
+        let mut random = Random(seed.max(1));
+        let mut errors = DerivativeErrors::default();
+
+        for _ in 0..samples {
+            let states: Vec<BodyState> = (0..self.bodies.len())
+                .map(|_| BodyState {
+                    position: random.vector(2.0),
+                    angle: random.next(std::f64::consts::PI),
+                    velocity: random.vector(1.0),
+                    angular_velocity: random.next(1.0),
+                    acceleration: random.vector(1.0),
+                })
+                .collect();
+
+            let row = self.j_row(expression, &states);
+            for body in 0..self.bodies.len() {
+                for coordinate in 0..DOF {
+                    let shifted = |step: f64| {
+                        let mut shifted = states.clone();
+                        match coordinate {
+                            0 => shifted[body].position.x += step,
+                            1 => shifted[body].position.y += step,
+                            _ => shifted[body].angle += step,
+                        }
+                        shifted
+                    };
+                    let derivative = (self.c(expression, &shifted(STEP)) - self.c(expression, &shifted(-STEP)))
+                        / (2.0 * STEP);
+                    let error = relative_error(row[body * DOF + coordinate], derivative);
+                    errors.j_row = errors.j_row.max(error);
+                }
+            }
+
+            let moved = |step: f64| states.iter().map(|state| state.moved(step)).collect::<Vec<_>>();
+            let c_dot = (self.c(expression, &moved(STEP)) - self.c(expression, &moved(-STEP))) / (2.0 * STEP);
+            errors.c_dot = errors.c_dot.max(relative_error(self.c_dot(expression, &states), c_dot));
+
+            let h = SECOND_ORDER_STEP;
+            let j_dot_q_dot = (self.c(expression, &moved(h)) - 2.0 * self.c(expression, &states)
+                + self.c(expression, &moved(-h)))
+                / (h * h);
+            errors.j_dot_q_dot = errors
+                .j_dot_q_dot
+                .max(relative_error(self.j_dot_q_dot(expression, &states), j_dot_q_dot));
+
+            let advanced = |time: f64| states.iter().map(|state| state.advanced(time)).collect::<Vec<_>>();
+            let third_order = (self.j_dot_q_dot(expression, &advanced(STEP))
+                - self.j_dot_q_dot(expression, &advanced(-STEP)))
+                / (2.0 * STEP);
+            errors.third_order = errors
+                .third_order
+                .max(relative_error(self.third_order(expression, &states), third_order));
+        }
+        errors
+    }
+}
+
+fn relative_error(analytic: f64, finite_difference: f64) -> f64 {
+    (analytic - finite_difference).abs() / finite_difference.abs().max(1.0)
+}
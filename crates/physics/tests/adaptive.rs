@@ -0,0 +1,51 @@
+mod common;
+
+use physics::AdaptiveStepping;
+use physics::solvers::{HybridV3, SolveError, Solver, SolverContext};
+use std::sync::Arc;
+
+/// ``HybridV3`` that gives up on steps longer than ``longest``, like a solver that diverges on a stiff scene
+struct ShortStepsOnly {
+    longest: f32,
+}
+
+impl Solver<f32> for ShortStepsOnly {
+    fn name(&self) -> &str {
+        "ShortStepsOnly"
+    }
+
+    fn solve(&self, ctx: &mut SolverContext<f32>) -> Result<(), SolveError> {
+        if ctx.time_step > self.longest {
+            return Err(SolveError::NotANumber);
+        }
+        HybridV3.solve(ctx)
+    }
+}
+
+#[test]
+fn stiff_springs_split_the_tick() {
+    let mut simulation = common::simulation::<f32>("bridge_soft", "FirstOrderSoft");
+    simulation.set_adaptive_stepping(Some(AdaptiveStepping::default()));
+    let mut most_substeps = 0;
+    for _ in 0..20 {
+        simulation.solve().unwrap();
+        most_substeps = most_substeps.max(simulation.take_snapshot().tick_substeps);
+    }
+    assert!(most_substeps > 1, "{most_substeps}");
+}
+
+#[test]
+fn failed_steps_are_tried_again_shorter() {
+    let mut simulation = common::simulation::<f32>("double", "HybridV3");
+    // the ticks of 8 ms have to be split in 4 at least
+    simulation.solver = Arc::new(ShortStepsOnly { longest: 0.0025 });
+    assert_eq!(simulation.solve(), Err(SolveError::NotANumber));
+
+    let mut simulation = common::simulation::<f32>("double", "HybridV3");
+    simulation.solver = Arc::new(ShortStepsOnly { longest: 0.0025 });
+    simulation.set_adaptive_stepping(Some(AdaptiveStepping::default()));
+    for _ in 0..20 {
+        simulation.solve().unwrap();
+        assert!(simulation.take_snapshot().tick_substeps >= 4);
+    }
+}
@@ -0,0 +1,37 @@
+//! Helpers shared by the integration tests, every test file doesn't use all of them
+#![allow(dead_code)]
+
+use physics::GameContent;
+use physics::scalar::{Real, real};
+use physics::scene::Scene;
+use physics::solvers::SolverRegistry;
+use std::path::Path;
+
+/// a scene of the ``scenes`` directory at the root of the repository
+pub fn scene(name: &str) -> Scene {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../scenes")
+        .join(name)
+        .with_extension("ron");
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}: {error}", path.display()));
+    Scene::from_ron(&text).unwrap_or_else(|error| panic!("{}: {error}", path.display()))
+}
+
+/// the scene ``name`` solved by ``solver``, with the ticks of 8 ms of the GUI
+pub fn simulation<T: Real>(name: &str, solver: &str) -> GameContent<T> {
+    let mut simulation = GameContent::empty(real(0.008));
+    simulation.solver = SolverRegistry::default()
+        .get(solver)
+        .unwrap_or_else(|| panic!("unknown solver {solver}"));
+    simulation.load_scene(&scene(name)).unwrap();
+    simulation
+}
+
+/// solve ``ticks`` ticks, the first error is returned with the tick it happened at
+pub fn run<T: Real>(simulation: &mut GameContent<T>, ticks: u32) {
+    for _ in 0..ticks {
+        if let Err(error) = simulation.solve() {
+            panic!("tick {}: {error}", simulation.age());
+        }
+    }
+}
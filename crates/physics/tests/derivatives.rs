@@ -0,0 +1,154 @@
+//! Check the derivatives of every built-in constraint with ``verification::DerivativeBench``,
+//! and that the bench catches a hand written derivative that is wrong.
+
+use hecs::{Entity, View};
+use nalgebra::Vector2;
+use physics::components::{Position, Rotation, SubjectToPhysic};
+use physics::constraints::{
+    AccelerationQuery, ConstraintDescription, ConstraintExpression, DOF, JacobianRow, VelocityQuery,
+};
+use physics::verification::{DerivativeBench, DerivativeErrors};
+
+const SAMPLES: usize = 200;
+const SEED: u64 = 42;
+/// the differences are good to about 1e-6, a wrong derivative is off by far more than this
+const TOLERANCE: f64 = 1e-4;
+
+fn check(description: &ConstraintDescription) -> DerivativeErrors {
+    let bodies = description.bodies().into_iter().max().map_or(0, |last| last + 1);
+    let mut bench = DerivativeBench::new(bodies);
+    let expression = description.build::<f64>(bench.bodies()).unwrap();
+    bench.check(expression.as_ref(), SAMPLES, SEED)
+}
+
+/// one constraint of every kind, with attachment points away from the centers
+fn every_constraint() -> Vec<ConstraintDescription> {
+    let local_a = Vector2::new(0.3, -0.2);
+    let local_b = Vector2::new(-0.1, 0.4);
+    let anchor = Vector2::new(0.5, 1.0);
+    let axis = Vector2::new(0.6, 0.8);
+    vec![
+        ConstraintDescription::Distance {
+            body_a: 0,
+            local_a,
+            body_b: 1,
+            local_b,
+            distance: 1.0,
+        },
+        ConstraintDescription::Anchor {
+            body: 0,
+            local: local_a,
+            anchor,
+            distance: 1.0,
+        },
+        ConstraintDescription::Plane {
+            body: 0,
+            normal: axis,
+            origin: 1.1,
+        },
+        ConstraintDescription::Pulley {
+            body_a: 0,
+            body_b: 1,
+            anchor_a: anchor,
+            anchor_b: -anchor,
+            distance: 3.0,
+        },
+        ConstraintDescription::Hinge {
+            body_a: 0,
+            local_a,
+            body_b: 1,
+            local_b,
+            axis,
+        },
+        ConstraintDescription::Pin {
+            body: 0,
+            local: local_a,
+            anchor,
+            axis,
+        },
+        ConstraintDescription::Gear {
+            body_a: 0,
+            body_b: 1,
+            ratio: 0.5,
+            phase: 0.3,
+        },
+    ]
+}
+
+#[test]
+fn derivatives_match_finite_differences() {
+    for description in every_constraint() {
+        let errors = check(&description);
+        assert!(errors.max() < TOLERANCE, "{description:?}: {errors}");
+    }
+}
+
+/// C = x², written by hand, with the third order term left out when ``forget_third_order`` is set
+struct Parabola {
+    body: Entity,
+    forget_third_order: bool,
+}
+
+impl ConstraintExpression<f64> for Parabola {
+    fn build_j_row(
+        &self,
+        bodies: &View<(&Position<f64>, &Rotation<f64>, &SubjectToPhysic)>,
+        row_view: &mut JacobianRow<f64>,
+    ) {
+        let (position, _, index) = bodies.get(self.body).unwrap();
+        row_view[index.0 * DOF] = 2.0 * position.actual.x;
+    }
+
+    fn compute_j_dot_q_dot(&self, bodies: &View<VelocityQuery<f64>>) -> f64 {
+        let (_, _, velocity, _) = bodies.get(self.body).unwrap();
+        2.0 * velocity.0.x * velocity.0.x
+    }
+
+    fn evaluate_c_dot(&self, bodies: &View<VelocityQuery<f64>>) -> f64 {
+        let (position, _, velocity, _) = bodies.get(self.body).unwrap();
+        2.0 * position.actual.x * velocity.0.x
+    }
+
+    fn evaluate_c(&self, bodies: &View<(&Position<f64>, &Rotation<f64>)>) -> f64 {
+        let (position, _) = bodies.get(self.body).unwrap();
+        position.actual.x * position.actual.x
+    }
+
+    fn compute_ddot_q_dot_plus_j_dot_q_ddot(&self, bodies: &View<AccelerationQuery<f64>>) -> f64 {
+        let (_, _, velocity, _, acceleration) = bodies.get(self.body).unwrap();
+        if self.forget_third_order {
+            0.0
+        } else {
+            4.0 * velocity.0.x * acceleration.0.x
+        }
+    }
+}
+
+#[test]
+fn hand_written_derivatives_are_checked() {
+    let mut bench = DerivativeBench::new(1);
+    let body = bench.bodies()[0];
+    let right = bench.check(
+        &Parabola {
+            body,
+            forget_third_order: false,
+        },
+        SAMPLES,
+        SEED,
+    );
+    assert!(right.max() < TOLERANCE, "{right}");
+
+    let wrong = bench.check(
+        &Parabola {
+            body,
+            forget_third_order: true,
+        },
+        SAMPLES,
+        SEED,
+    );
+    assert!(wrong.third_order > 0.1, "{wrong}");
+    assert_eq!(
+        (wrong.j_row, wrong.c_dot, wrong.j_dot_q_dot),
+        (right.j_row, right.c_dot, right.j_dot_q_dot)
+    );
+}
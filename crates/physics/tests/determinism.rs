@@ -0,0 +1,53 @@
+mod common;
+
+use physics::determinism::TickHash;
+use physics::scalar::sin_cos;
+
+/// position of ``x`` among the f64 in increasing order, two neighbours are 1 apart
+fn ordered(x: f64) -> i64 {
+    let bits = x.to_bits() as i64;
+    if bits < 0 { i64::MIN - bits } else { bits }
+}
+
+#[test]
+fn sin_cos_is_within_an_ulp_of_the_standard_library() {
+    let mut worst = 0;
+    for i in -200_000..=200_000 {
+        // not a round step, so the sweep doesn't only hit the same fractions of π
+        let x = i as f64 * 1.234_567e-3;
+        let (sin, cos) = sin_cos(x);
+        let (expected_sin, expected_cos) = x.sin_cos();
+        for (value, expected) in [(sin, expected_sin), (cos, expected_cos)] {
+            worst = worst.max((ordered(value) - ordered(expected)).abs());
+        }
+    }
+    assert!(worst <= 1, "{worst} ulps");
+}
+
+/// the hash stream of ``ticks`` ticks of ``scene``, run like the GUI does it
+fn hash_stream(scene: &str, solver: &str, ticks: u32) -> Vec<TickHash> {
+    let mut simulation = common::simulation::<f32>(scene, solver);
+    simulation.set_deterministic(true);
+    for _ in 0..ticks {
+        simulation.solve().unwrap();
+        let snapshot = simulation.take_snapshot();
+        assert_eq!(
+            snapshot.state_hash,
+            simulation.hash_stream().last().map(|hash| hash.hash)
+        );
+    }
+    simulation.hash_stream().to_vec()
+}
+
+#[test]
+fn same_scene_gives_the_same_hash_stream() {
+    for (scene, solver) in [
+        ("triple", "HybridV3"),
+        ("stack", "SequentialImpulse8"),
+        ("gears", "Rattle16"),
+    ] {
+        let first = hash_stream(scene, solver, 300);
+        assert_eq!(first.len(), 300);
+        assert_eq!(first, hash_stream(scene, solver, 300), "{scene} {solver}");
+    }
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use nalgebra::Vector2;
+use physics::GameContent;
+use physics::components::Position;
+use physics::constraints::{AnchorConstraint, DistanceConstraint};
+use physics::solvers::{ConjugateGradient, HybridV3cgm, Preconditioner, SolveError};
+use std::sync::Arc;
+
+/// a pendulum hanging from the origin, with a body of ``mass`` at its end
+fn pendulum(mass: f64) -> GameContent<f64> {
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    let body = simulation.add_body(Vector2::new(1.0, 0.0), Vector2::zeros(), mass);
+    simulation.add_stiff_constraint(AnchorConstraint {
+        body,
+        local: Vector2::zeros(),
+        anchor: Vector2::zeros(),
+        distance: 1.0,
+    });
+    simulation
+}
+
+#[test]
+fn constraint_on_a_despawned_body_is_reported() {
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    let body = simulation.add_body(Vector2::new(1.0, 0.0), Vector2::zeros(), 1.0);
+    let other = simulation.add_body(Vector2::new(2.0, 0.0), Vector2::zeros(), 1.0);
+    let constraint = simulation.add_stiff_constraint(DistanceConstraint {
+        body_a: body,
+        local_a: Vector2::zeros(),
+        body_b: other,
+        local_b: Vector2::zeros(),
+        distance: 1.0,
+    });
+    // despawned behind the back of ``remove_body``, which would have removed the constraint
+    simulation.world.despawn(other).unwrap();
+    assert_eq!(
+        simulation.solve(),
+        Err(SolveError::MissingBody {
+            constraint,
+            body: other
+        })
+    );
+}
+
+#[test]
+fn position_that_isnt_a_number_is_reported() {
+    let mut simulation = pendulum(1.0);
+    common::run(&mut simulation, 10);
+    for (_, position) in simulation.world.query_mut::<&mut Position<f64>>() {
+        position.actual.x = f64::NAN;
+    }
+    assert_eq!(simulation.solve(), Err(SolveError::NotANumber));
+}
+
+#[test]
+fn negative_mass_cant_be_factorised() {
+    let mut simulation = pendulum(-1.0);
+    assert_eq!(simulation.solve(), Err(SolveError::Factorisation));
+}
+
+#[test]
+fn conjugate_gradient_stops_at_its_iteration_cap() {
+    let mut simulation = common::simulation::<f64>("bridge", "HybridV3");
+    simulation.solver = Arc::new(HybridV3cgm::new(ConjugateGradient {
+        tolerance: 1e-12,
+        max_iterations: 1,
+        preconditioner: Preconditioner::None,
+    }));
+    assert_eq!(simulation.solve(), Err(SolveError::IterationLimit { iterations: 1 }));
+}
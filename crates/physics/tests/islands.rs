@@ -0,0 +1,54 @@
+mod common;
+
+use physics::GameContent;
+use physics::persistence::SavedState;
+use physics::scene::Scene;
+use physics::solvers::SolverRegistry;
+
+/// two pendulums hanging from the same static body, a solver can't couple them through it
+const TWO_PENDULUMS: &str = "(
+    bodies: [
+        (kind: Static),
+        (position: (1.0, 0.0)),
+        (position: (-1.0, 0.0)),
+        (position: (-1.0, -1.0), velocity: (1.0, 0.0)),
+    ],
+    constraints: [
+        (constraint: Distance(body_a: 0, body_b: 1, distance: 1.0)),
+        (constraint: Distance(body_a: 0, body_b: 2, distance: 1.0)),
+        (constraint: Distance(body_a: 2, body_b: 3, distance: 1.0)),
+    ],
+)";
+
+#[test]
+fn a_shared_static_body_doesnt_join_islands() {
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    simulation.load_scene(&Scene::from_ron(TWO_PENDULUMS).unwrap()).unwrap();
+    simulation.solve().unwrap();
+    assert_eq!(simulation.take_snapshot().islands, 2);
+}
+
+/// the state after ``ticks`` ticks of ``scene``, solved with the islands on one thread or on several
+fn final_state(scene: &Scene, solver: &str, parallel: bool, ticks: u32) -> SavedState {
+    let mut simulation = GameContent::<f32>::empty(0.008);
+    simulation.solver = SolverRegistry::default().get(solver).unwrap();
+    simulation.set_parallel_islands(parallel);
+    simulation.load_scene(scene).unwrap();
+    common::run(&mut simulation, ticks);
+    simulation.save_state().unwrap()
+}
+
+#[test]
+fn parallel_islands_give_the_serial_result() {
+    let two_pendulums = Scene::from_ron(TWO_PENDULUMS).unwrap();
+    for (scene, solver) in [
+        (&two_pendulums, "HybridV3"),
+        (&common::scene("stack"), "SequentialImpulse8"),
+        (&common::scene("impact"), "HybridV3"),
+    ] {
+        let serial = final_state(scene, solver, false, 200);
+        let parallel = final_state(scene, solver, true, 200);
+        assert_eq!(serial.bodies, parallel.bodies, "{solver}");
+        assert_eq!(serial.applied_correction, parallel.applied_correction, "{solver}");
+    }
+}
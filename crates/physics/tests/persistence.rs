@@ -0,0 +1,57 @@
+mod common;
+
+use physics::persistence::SavedState;
+use physics::solvers::SolverRegistry;
+use physics::{AdaptiveStepping, GameContent};
+
+/// save ``scene`` after ``before`` ticks, load it in a new simulation, and check both give the same hashes for
+/// ``after`` more ticks
+fn check_round_trip(scene: &str, solver: &str, before: u32, after: u32) {
+    let mut original = common::simulation::<f32>(scene, solver);
+    original.set_deterministic(true);
+    original.set_parallel_islands(true);
+    common::run(&mut original, before);
+
+    let saved = original.save_state().unwrap();
+    let text = saved.to_ron().unwrap();
+    // the static and kinematic bodies have an infinite mass, it must survive the text format
+    assert_eq!(SavedState::from_ron(&text).unwrap(), saved, "{scene}");
+
+    let mut loaded = GameContent::<f32>::empty(0.016);
+    loaded.set_adaptive_stepping(Some(AdaptiveStepping::default()));
+    loaded
+        .load_state(&SavedState::from_ron(&text).unwrap(), &SolverRegistry::default())
+        .unwrap();
+    assert_eq!(loaded.adaptive_stepping(), None);
+    assert!(loaded.deterministic() && loaded.parallel_islands());
+
+    common::run(&mut original, after);
+    common::run(&mut loaded, after);
+    assert_eq!(
+        original.hash_stream()[before as usize..],
+        *loaded.hash_stream(),
+        "{scene} {solver}"
+    );
+}
+
+#[test]
+fn loaded_state_goes_on_like_the_saved_one() {
+    // a static body and a kinematic one
+    check_round_trip("driven", "HybridV3", 100, 200);
+    // contacts and walls, warm started
+    check_round_trip("stack", "SequentialImpulse8", 100, 200);
+    check_round_trip("floor", "Tgs", 100, 200);
+}
+
+#[test]
+fn infinite_mass_is_saved() {
+    let mut simulation = common::simulation::<f64>("driven", "HybridV3");
+    let saved = simulation.save_state().unwrap();
+    assert!(
+        saved
+            .bodies
+            .iter()
+            .any(|body| body.mass == f64::INFINITY && body.inv_mass == 0.0)
+    );
+    assert_eq!(SavedState::from_ron(&saved.to_ron().unwrap()).unwrap(), saved);
+}
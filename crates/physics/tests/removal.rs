@@ -0,0 +1,100 @@
+mod common;
+
+use hecs::Entity;
+use nalgebra::Vector2;
+use physics::GameContent;
+use physics::components::SubjectToPhysic;
+use physics::constraints::{AnchorConstraint, DistanceConstraint};
+
+/// a double pendulum made of ``bodies[0]`` and ``bodies[1]`` hanging from the origin, ``bodies[2]`` hanging from the
+/// end of the second one, and ``bodies[3]`` a simple pendulum of its own, each constraint pulling at some point
+fn chain() -> (GameContent<f64>, Vec<Entity>) {
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    let bodies: Vec<Entity> = [(1.0, 0.0), (2.0, 0.0), (2.0, -1.0), (-1.0, 0.0)]
+        .into_iter()
+        .map(|(x, y)| simulation.add_body(Vector2::new(x, y), Vector2::zeros(), 1.0))
+        .collect();
+    simulation.add_stiff_constraint(AnchorConstraint {
+        body: bodies[0],
+        local: Vector2::zeros(),
+        anchor: Vector2::zeros(),
+        distance: 1.0,
+    });
+    for (a, b) in [(0, 1), (1, 2)] {
+        simulation.add_stiff_constraint(DistanceConstraint {
+            body_a: bodies[a],
+            local_a: Vector2::zeros(),
+            body_b: bodies[b],
+            local_b: Vector2::zeros(),
+            distance: 1.0,
+        });
+    }
+    simulation.add_stiff_constraint(AnchorConstraint {
+        body: bodies[3],
+        local: Vector2::zeros(),
+        anchor: Vector2::new(-2.0, 0.0),
+        distance: 1.0,
+    });
+    common::run(&mut simulation, 20);
+    (simulation, bodies)
+}
+
+fn index(simulation: &GameContent<f64>, body: Entity) -> usize {
+    simulation.world.get::<&SubjectToPhysic>(body).unwrap().0
+}
+
+#[test]
+fn removing_a_middle_body_reindexes_the_others() {
+    let (mut simulation, bodies) = chain();
+    let warm_start = simulation.save_state().unwrap().applied_correction;
+    assert!(warm_start.iter().all(|&lambda| lambda != 0.0));
+
+    simulation.remove_body(bodies[1]).unwrap();
+    let indices: Vec<usize> = [0, 2, 3].iter().map(|&i| index(&simulation, bodies[i])).collect();
+    assert_eq!(indices, [0, 1, 2]);
+    // both distance constraints held the removed body, the anchors keep their warm start
+    let state = simulation.save_state().unwrap();
+    assert_eq!(state.constraints.len(), 2);
+    assert_eq!(state.applied_correction, [warm_start[0], warm_start[3]]);
+    common::run(&mut simulation, 20);
+}
+
+#[test]
+fn removing_a_middle_constraint_shifts_the_warm_start() {
+    let (mut simulation, _) = chain();
+    let before = simulation.save_state().unwrap();
+
+    simulation.remove_constraint(1);
+    let after = simulation.save_state().unwrap();
+    let kept = [0, 2, 3];
+    assert_eq!(after.constraints, kept.map(|i| before.constraints[i].clone()));
+    assert_eq!(after.applied_correction, kept.map(|i| before.applied_correction[i]));
+    common::run(&mut simulation, 20);
+}
+
+#[test]
+fn removing_a_body_in_contact_removes_its_contacts() {
+    // ``bodies[1]`` rests on ``bodies[0]``, which rests on the floor
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    simulation.add_wall(Vector2::new(0.0, 1.0), Vector2::zeros());
+    let bodies: Vec<Entity> = [0.5, 1.5]
+        .into_iter()
+        .map(|y| {
+            let body = simulation.add_body(Vector2::new(0.0, y), Vector2::zeros(), 1.0);
+            simulation.add_collider(body, 0.5);
+            body
+        })
+        .collect();
+    common::run(&mut simulation, 20);
+    // the contact with the floor comes first, then the one between the bodies
+    let warm_start = simulation.save_state().unwrap().applied_correction;
+    assert_eq!(warm_start.len(), 2);
+    assert!(warm_start.iter().all(|&lambda| lambda != 0.0));
+
+    simulation.remove_body(bodies[1]).unwrap();
+    let snapshot = simulation.take_snapshot();
+    assert_eq!(snapshot.pos.len(), 1);
+    assert!(snapshot.violation_mean.abs() < 1e-3, "{}", snapshot.violation_mean);
+    assert_eq!(simulation.save_state().unwrap().applied_correction, [warm_start[0]]);
+    common::run(&mut simulation, 20);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use physics::GameContent;
+use physics::persistence::StateError;
+use physics::scene::Scene;
+use std::path::Path;
+
+#[test]
+fn every_scene_loads_and_runs() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../scenes");
+    let mut names: Vec<String> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert!(!names.is_empty());
+    for name in names {
+        let mut simulation = common::simulation::<f32>(&name, "HybridV3");
+        common::run(&mut simulation, 50);
+        let snapshot = simulation.take_snapshot();
+        assert!(
+            snapshot.pos.iter().all(|pos| pos.x.is_finite() && pos.y.is_finite()),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn plane_normal_is_normalised_with_its_origin() {
+    // the plane y = 1, written with a normal of length 2: C is the distance to the plane all the same
+    let scene = Scene::from_ron(
+        "(
+            bodies: [(position: (3.0, 1.5))],
+            constraints: [(constraint: Plane(body: 0, normal: (0.0, 2.0), origin: 2.0))],
+        )",
+    )
+    .unwrap();
+    let mut simulation = GameContent::<f64>::empty(0.008);
+    simulation.load_scene(&scene).unwrap();
+    assert!((simulation.take_snapshot().violation_mean - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn bodies_that_cant_be_simulated_are_rejected() {
+    let cases = [
+        ("(mass: 0.0)", "mass"),
+        ("(mass: -1.0)", "mass"),
+        ("(mass: inf)", "mass"),
+        ("(mass: NaN)", "mass"),
+        ("(inertia: Some(0.0))", "inertia"),
+        ("(inertia: Some(inf))", "inertia"),
+        ("(collider: Some(0.0))", "collider radius"),
+        ("(collider: Some(-0.5))", "collider radius"),
+    ];
+    for (body, field) in cases {
+        let scene = Scene::from_ron(&format!("(bodies: [(), {body}])")).unwrap();
+        let mut simulation = GameContent::<f32>::empty(0.008);
+        assert_eq!(
+            simulation.load_scene(&scene),
+            Err(StateError::InvalidBody { body: 1, field }),
+            "{body}"
+        );
+    }
+
+    // a static body has an infinite mass whatever the file says
+    let scene = Scene::from_ron("(bodies: [(kind: Static, mass: 0.0)])").unwrap();
+    assert_eq!(GameContent::<f32>::empty(0.008).load_scene(&scene), Ok(()));
+}
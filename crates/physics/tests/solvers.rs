@@ -0,0 +1,78 @@
+mod common;
+
+use common::{run, scene, simulation};
+use physics::GameContent;
+use physics::scene::Scene;
+use physics::solvers::SolverRegistry;
+
+#[test]
+fn rattle_keeps_stiff_springs_stable() {
+    // the springs of bridge_soft are stiff enough to make an explicit spring force diverge within a few ticks
+    let mut bridge = simulation::<f64>("bridge_soft", "Rattle16");
+    run(&mut bridge, 1000);
+    let state = bridge.save_state().unwrap();
+    for (index, (body, start)) in state
+        .bodies
+        .iter()
+        .zip(&scene("bridge_soft").bodies)
+        .enumerate()
+        .take(12)
+    {
+        let sag = (body.position - start.position).norm();
+        assert!(sag < 0.5, "node {index} of the bridge moved by {sag}");
+    }
+}
+
+#[test]
+fn hybrid_v4_follows_the_scene_gravity() {
+    // a weightless pendulum turns at a constant speed, the gravity of the GUI scenes mustn't slip into the
+    // third order term
+    let scene = Scene::from_ron(
+        "(
+            gravity: (0.0, 0.0),
+            bodies: [(position: (1.0, 0.0), velocity: (0.0, 1.0), mass: 3.0)],
+            constraints: [(constraint: Anchor(body: 0, anchor: (0.0, 0.0), distance: 1.0))],
+        )",
+    )
+    .unwrap();
+    let mut pendulum = GameContent::<f64>::empty(0.008);
+    pendulum.solver = SolverRegistry::default().get("HybridV4").unwrap();
+    pendulum.load_scene(&scene).unwrap();
+    let start = pendulum.take_snapshot().kinetic_energy;
+    run(&mut pendulum, 500);
+    let end = pendulum.take_snapshot().kinetic_energy;
+    assert!((end - start).abs() < 2e-4 * start, "{start} {end}");
+}
+
+/// a double pendulum, with its second bar twice when ``duplicated`` is set
+fn double_pendulum(solver: &str, duplicated: bool) -> GameContent<f64> {
+    let bar = "(constraint: Distance(body_a: 0, body_b: 1, distance: 1.0)),";
+    let scene = Scene::from_ron(&format!(
+        "(
+            bodies: [(position: (1.0, 0.0)), (position: (2.0, 0.0))],
+            constraints: [(constraint: Anchor(body: 0, anchor: (0.0, 0.0), distance: 1.0)), {bar} {}],
+        )",
+        if duplicated { bar } else { "" }
+    ))
+    .unwrap();
+    let mut pendulum = GameContent::<f64>::empty(0.008);
+    pendulum.solver = SolverRegistry::default().get(solver).unwrap();
+    pendulum.load_scene(&scene).unwrap();
+    pendulum
+}
+
+#[test]
+fn duplicated_constraint_is_reported_and_left_out() {
+    for solver in ["HybridV3", "FirstOrder", "ImplicitEuler8"] {
+        let mut duplicated = double_pendulum(solver, true);
+        let mut single = double_pendulum(solver, false);
+        run(&mut duplicated, 200);
+        run(&mut single, 200);
+        assert_eq!(duplicated.take_snapshot().redundant_constraints, [2], "{solver}");
+        // the duplicate gets a multiplier of 0, the pendulum moves as if it wasn't there
+        let (duplicated, single) = (duplicated.save_state().unwrap(), single.save_state().unwrap());
+        for (a, b) in duplicated.bodies.iter().zip(&single.bodies) {
+            assert!((a.position - b.position).norm() < 1e-6, "{solver}: {a:?} {b:?}");
+        }
+    }
+}
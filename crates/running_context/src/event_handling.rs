@@ -181,7 +181,7 @@ impl<Graphic: GraphicHandler, Logic: LogicHandler> ApplicationHandler
                     window,
                     false,
                 );
-                let input = egui_state.take_egui_input(&window);
+                let input = egui_state.take_egui_input(window);
 
                 let egui::FullOutput {
                     platform_output,
@@ -211,7 +211,7 @@ impl<Graphic: GraphicHandler, Logic: LogicHandler> ApplicationHandler
                     }
                 }
 
-                egui_state.handle_platform_output(&window, platform_output);
+                egui_state.handle_platform_output(window, platform_output);
                 window.request_redraw();
             }
             _ => {}
@@ -230,11 +230,8 @@ impl<Graphic: GraphicHandler, Logic: LogicHandler> ApplicationHandler
         let Some(DeferredInit { egui_state, .. }) = &mut self.deferred_init else {
             return;
         };
-        match event {
-            DeviceEvent::MouseMotion { delta } => {
-                egui_state.on_mouse_motion(delta);
-            }
-            _ => (),
+        if let DeviceEvent::MouseMotion { delta } = event {
+            egui_state.on_mouse_motion(delta);
         }
     }
 
@@ -7,13 +7,13 @@ pub struct DeviceHandle {
 }
 
 impl DeviceHandle {
-    async fn new<'s>(ctx: &RenderContext, surface: &wgpu::Surface<'_>) -> Self {
+    async fn new(ctx: &RenderContext, surface: &wgpu::Surface<'_>) -> Self {
         let adapter = ctx
             .instance
             .request_adapter(&wgpu::RequestAdapterOptions {
                 power_preference: Default::default(),
                 force_fallback_adapter: false, //don't know why, window do not like the fallback adapter
-                compatible_surface: Some(&surface),
+                compatible_surface: Some(surface),
             })
             .await
             .unwrap();
@@ -88,11 +88,11 @@ impl RenderContext {
     /** get a suitable device for a given Surface, or create one, and return the associated ID**/
     async fn device_for_surface(&mut self, surface: &wgpu::Surface<'_>) {
         if let Some(device) = &self.device {
-            device.adapter.is_surface_supported(&surface);
+            device.adapter.is_surface_supported(surface);
             return;
         }
 
-        self.device = Some(DeviceHandle::new(&self, surface).await);
+        self.device = Some(DeviceHandle::new(self, surface).await);
     }
 
     /** create a surface with a given present mode, if not supported, another mod will be used in fallback...
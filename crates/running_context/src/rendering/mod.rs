@@ -19,6 +19,12 @@ pub struct Graphic<'s> {
     surface: Option<RenderSurface<'s>>,
 }
 
+impl<'s> Default for Graphic<'s> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'s> Graphic<'s> {
     pub fn new() -> Self {
         Self {
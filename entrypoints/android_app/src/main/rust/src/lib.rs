@@ -8,6 +8,10 @@ pub fn main(android_app: winit::platform::android::activity::AndroidApp) {
     use winit::event_loop::EventLoop;
     use winit::platform::android::EventLoopBuilderExtAndroid;
 
+    // the GUI writes the saved state and the exported hashes in the current directory, the root isn't writable
+    if let Some(path) = android_app.internal_data_path() {
+        let _ = std::env::set_current_dir(path);
+    }
     let mut app = new_app();
 
     EventLoop::with_user_event()